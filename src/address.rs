@@ -0,0 +1,188 @@
+use std::fmt;
+use std::str::FromStr;
+
+use bitcoin::address::NetworkUnchecked;
+use bitcoin::{Address, Network};
+
+/// Kind of a Bitcoin address, derived from its prefix and length
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AddressType {
+    /// Legacy address starting with "1"
+    P2pkh,
+    /// Script hash address starting with "3"
+    P2sh,
+    /// Native SegWit key hash address, "bc1q" and 42 characters long
+    P2wpkh,
+    /// Native SegWit script hash address, "bc1q" and 62 characters long
+    P2wsh,
+    /// SegWit v1 address starting with "bc1p"
+    Taproot,
+    Unknown,
+}
+
+impl fmt::Display for AddressType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let description = match self {
+            AddressType::P2pkh => "P2PKH (Legacy)",
+            AddressType::P2sh => "P2SH (Script Hash)",
+            AddressType::P2wpkh => "P2WPKH (Bech32 SegWit)",
+            AddressType::P2wsh => "P2WSH (Bech32 SegWit)",
+            AddressType::Taproot => "P2TR (Taproot)",
+            AddressType::Unknown => "Unknown",
+        };
+        write!(f, "{description}")
+    }
+}
+
+/// Detects the type of a mainnet or testnet address from its prefix/encoding
+pub fn classify_address_type(address: &str) -> AddressType {
+    // Bech32 addresses are case insensitive
+    let lowercase = address.to_lowercase();
+
+    if lowercase.starts_with("bc1q") || lowercase.starts_with("tb1q") {
+        match address.len() {
+            42 => AddressType::P2wpkh,
+            62 => AddressType::P2wsh,
+            _ => AddressType::Unknown,
+        }
+    } else if lowercase.starts_with("bc1p") || lowercase.starts_with("tb1p") {
+        AddressType::Taproot
+    } else if address.starts_with('1') || address.starts_with(['m', 'n']) {
+        AddressType::P2pkh
+    } else if address.starts_with('3') || address.starts_with('2') {
+        AddressType::P2sh
+    } else {
+        AddressType::Unknown
+    }
+}
+
+/// Checks the encoding and checksum of an address, and that it belongs to mainnet,
+/// or to testnet (`m`/`n`/`2`/`tb1` prefixes) when `testnet` is set
+pub fn validate_address(address: &str, testnet: bool) -> Result<(), String> {
+    let network = if testnet {
+        Network::Testnet
+    } else {
+        Network::Bitcoin
+    };
+
+    let address = Address::<NetworkUnchecked>::from_str(address)
+        .map_err(|error| format!("not a valid Bitcoin address ({error})"))?;
+
+    if !address.is_valid_for_network(network) {
+        return Err(if testnet {
+            "it's a mainnet address, drop --testnet to inspect it".to_string()
+        } else {
+            "it's a testnet address, use --testnet to inspect it".to_string()
+        });
+    }
+
+    Ok(())
+}
+
+/// How many of a list of addresses (i.e the inputs of a transaction) are
+/// legacy, SegWit or Taproot
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct ScriptTally {
+    pub legacy: usize,
+    // P2SH can be legacy multisig or wrapped SegWit, the address alone can't tell
+    pub script_hash: usize,
+    pub segwit: usize,
+    pub taproot: usize,
+    // Inputs without an address (non-standard scripts) end up here too
+    pub unknown: usize,
+}
+
+impl ScriptTally {
+    pub fn from_addresses<'a>(addresses: impl IntoIterator<Item = Option<&'a str>>) -> ScriptTally {
+        let mut tally = ScriptTally::default();
+
+        for address in addresses {
+            match address.map(classify_address_type) {
+                Some(AddressType::P2pkh) => tally.legacy += 1,
+                Some(AddressType::P2sh) => tally.script_hash += 1,
+                Some(AddressType::P2wpkh | AddressType::P2wsh) => tally.segwit += 1,
+                Some(AddressType::Taproot) => tally.taproot += 1,
+                Some(AddressType::Unknown) | None => tally.unknown += 1,
+            }
+        }
+
+        tally
+    }
+}
+
+impl fmt::Display for ScriptTally {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let parts: Vec<String> = [
+            (self.segwit, "SegWit"),
+            (self.taproot, "Taproot"),
+            (self.legacy, "Legacy"),
+            (self.script_hash, "P2SH (Legacy or wrapped SegWit)"),
+            (self.unknown, "Unknown"),
+        ]
+        .iter()
+        .filter(|(count, _)| *count > 0)
+        .map(|(count, kind)| format!("{count} {kind}"))
+        .collect();
+
+        write!(f, "{}", parts.join(", "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tallies_the_script_types() {
+        let tally = ScriptTally::from_addresses([
+            Some("1A1zP1eP5QGefi2DMPTfTL5SLmv7DivfNa"),
+            Some("bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdq"),
+            Some("bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdq"),
+            Some("bc1p5d7rjq7g6rdk2yhzks9smlaqtedr4dekq08ge8ztwac72sfr9rusxg3297"),
+            None,
+        ]);
+
+        assert_eq!(
+            tally.to_string(),
+            "2 SegWit, 1 Taproot, 1 Legacy, 1 Unknown"
+        );
+    }
+
+    #[test]
+    fn classifies_every_mainnet_prefix() {
+        assert_eq!(
+            classify_address_type("1A1zP1eP5QGefi2DMPTfTL5SLmv7DivfNa"),
+            AddressType::P2pkh
+        );
+        assert_eq!(
+            classify_address_type("3J98t1WpEZ73CNmQviecrnyiWrnqRhWNLy"),
+            AddressType::P2sh
+        );
+        assert_eq!(
+            classify_address_type("bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdq"),
+            AddressType::P2wpkh
+        );
+        assert_eq!(
+            classify_address_type("bc1q9jk7j04lcjzdns6wjegeju78mxq27reg5e4wkycrs407yd0r07psvs8x7u"),
+            AddressType::P2wsh
+        );
+        assert_eq!(
+            classify_address_type("bc1p5d7rjq7g6rdk2yhzks9smlaqtedr4dekq08ge8ztwac72sfr9rusxg3297"),
+            AddressType::Taproot
+        );
+        assert_eq!(classify_address_type("xyz"), AddressType::Unknown);
+    }
+
+    #[test]
+    fn testnet_addresses_need_testnet() {
+        let testnet_address = "tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx";
+        let mainnet_address = "bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdq";
+
+        assert_eq!(classify_address_type(testnet_address), AddressType::P2wpkh);
+        assert!(validate_address(testnet_address, true).is_ok());
+        assert!(validate_address(testnet_address, false).is_err());
+        assert!(validate_address(mainnet_address, false).is_ok());
+        assert!(validate_address(mainnet_address, true).is_err());
+        assert!(validate_address("bc1qnotanaddress", false).is_err());
+    }
+}
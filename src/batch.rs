@@ -0,0 +1,91 @@
+use std::collections::{HashMap, HashSet};
+use std::num::NonZeroUsize;
+use std::pin::Pin;
+
+use futures::{Stream, StreamExt};
+use indicatif::ProgressBar;
+
+use crate::http::HttpClient;
+use crate::{inspect_transaction, Transaction};
+
+/// A transaction fetched with its raw response
+type Fetched = Result<(Transaction, String), Box<dyn std::error::Error>>;
+
+/// Transactions of a batch of hashes, fetched `concurrency` at a time and handed
+/// back in the order of the batch as soon as the ones before them are in, so the
+/// first ones can be shown while the next ones are still being fetched.
+///
+/// A hash repeated in the batch is fetched once, unless its first request failed
+pub struct TransactionBatch<'a> {
+    fetching: Pin<Box<dyn Stream<Item = Fetched> + 'a>>,
+    // Hashes already taken from `fetching`
+    requested: HashSet<String>,
+    cache: HashMap<String, (Transaction, String)>,
+    inspect_transaction_url: &'a str,
+    http: &'a HttpClient,
+    progress: ProgressBar,
+}
+
+impl<'a> TransactionBatch<'a> {
+    /// Starts fetching `hashes`, `progress` advances on every transaction fetched
+    pub fn new(
+        hashes: &'a [String],
+        inspect_transaction_url: &'a str,
+        http: &'a HttpClient,
+        concurrency: NonZeroUsize,
+        progress: ProgressBar,
+    ) -> TransactionBatch<'a> {
+        let mut seen = HashSet::new();
+        let distinct: Vec<&String> = hashes.iter().filter(|hash| seen.insert(*hash)).collect();
+
+        let fetched = progress.clone();
+        let fetching = futures::stream::iter(distinct)
+            .map(move |hash| {
+                let fetched = fetched.clone();
+                async move {
+                    let transaction =
+                        inspect_transaction(hash, inspect_transaction_url, http).await;
+                    fetched.inc(1);
+                    transaction
+                }
+            })
+            .buffered(concurrency.get());
+
+        TransactionBatch {
+            fetching: Box::pin(fetching),
+            requested: HashSet::new(),
+            cache: HashMap::new(),
+            inspect_transaction_url,
+            http,
+            progress,
+        }
+    }
+
+    /// The transaction of `hash`, the next hash of the batch
+    pub async fn next(&mut self, hash: &str) -> Fetched {
+        if let Some(cached) = self.cache.get(hash) {
+            return Ok(cached.clone());
+        }
+
+        let fetched = if self.requested.insert(hash.to_string()) {
+            self.fetching
+                .next()
+                .await
+                .unwrap_or_else(|| Err(format!("{hash} isn't part of the batch").into()))
+        } else {
+            // Repeated after a failure, it is tried again
+            inspect_transaction(hash, self.inspect_transaction_url, self.http).await
+        };
+
+        if let Ok(fetched) = &fetched {
+            self.cache.insert(hash.to_string(), fetched.clone());
+        }
+
+        fetched
+    }
+
+    /// Clears the progress bar once the batch is done
+    pub fn finish(self) {
+        self.progress.finish_and_clear();
+    }
+}
@@ -0,0 +1,265 @@
+use ansi_term::Color;
+use chrono::{DateTime, Utc};
+use cli_table::{Cell, Style, Table};
+use futures::{StreamExt, TryStreamExt};
+use num_format::ToFormattedString;
+use serde::{Deserialize, Serialize};
+
+use crate::fees::fee_rate;
+use crate::format::format_btc;
+use crate::http::HttpClient;
+use crate::output::{
+    amount_cell, number_format, paint, print_table, summary_border, summary_style,
+};
+use crate::{print_result, Cli, OutputFormat, Transaction};
+
+/// Most blocks a range can span, a block weighs up to a few MB of JSON
+const MAX_BLOCK_RANGE: u64 = 100;
+
+/// Response of the block-height endpoint, a height can hold several blocks during a reorg
+#[derive(Debug, Deserialize)]
+struct BlockHeightResponse {
+    blocks: Vec<Block>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Block {
+    hash: String,
+    height: u64,
+    time: i64, // Unix timestamp
+    #[serde(default)]
+    tx: Vec<Transaction>,
+}
+
+/// What a block moved and paid in fees, the amounts are in satoshis and the
+/// coinbase transaction is left out of both
+#[derive(Debug, Serialize)]
+pub struct BlockSummary {
+    height: u64,
+    hash: String,
+    time: Option<DateTime<Utc>>,
+    transactions: usize,
+    moved_satoshis: u64,
+    fees_satoshis: u64,
+    vsize: u64,
+    fee_rate: Option<f64>, // sat/vByte
+}
+
+impl BlockSummary {
+    fn from_block(block: &Block) -> BlockSummary {
+        let paying = || block.tx.iter().filter(|tx| !tx.is_coinbase());
+        let fees_satoshis = paying().filter_map(Transaction::fee).sum();
+        let vsize = paying().map(Transaction::vsize).sum();
+
+        BlockSummary {
+            height: block.height,
+            hash: block.hash.clone(),
+            time: DateTime::from_timestamp(block.time, 0),
+            transactions: block.tx.len(),
+            moved_satoshis: paying().map(Transaction::total_output).sum(),
+            fees_satoshis,
+            vsize,
+            fee_rate: fee_rate(fees_satoshis, vsize),
+        }
+    }
+}
+
+/// Sums of a range of blocks
+#[derive(Debug, Serialize)]
+struct RangeTotals {
+    blocks: usize,
+    transactions: usize,
+    moved_satoshis: u64,
+    fees_satoshis: u64,
+    fee_rate: Option<f64>, // sat/vByte, weighted by the size of the transactions
+}
+
+impl RangeTotals {
+    fn of(blocks: &[BlockSummary]) -> RangeTotals {
+        let fees_satoshis = blocks.iter().map(|block| block.fees_satoshis).sum();
+
+        RangeTotals {
+            blocks: blocks.len(),
+            transactions: blocks.iter().map(|block| block.transactions).sum(),
+            moved_satoshis: blocks.iter().map(|block| block.moved_satoshis).sum(),
+            fees_satoshis,
+            fee_rate: fee_rate(fees_satoshis, blocks.iter().map(|block| block.vsize).sum()),
+        }
+    }
+}
+
+/// Checks that `from..=to` is a range of at most MAX_BLOCK_RANGE blocks
+pub fn check_range(from: u64, to: u64) -> Result<(), String> {
+    if from > to {
+        return Err(format!(
+            "[!] --from {from} is above --to {to}, the range goes up"
+        ));
+    }
+
+    if to - from >= MAX_BLOCK_RANGE {
+        return Err(format!(
+            "[!] The range spans {} blocks, at most {MAX_BLOCK_RANGE} are fetched at once",
+            to - from + 1
+        ));
+    }
+
+    Ok(())
+}
+
+/// URL of every block of the range, in height order
+pub fn block_urls(api_base: &str, from: u64, to: u64) -> Vec<String> {
+    (from..=to)
+        .map(|height| format!("{api_base}/block-height/{height}?format=json"))
+        .collect()
+}
+
+/// Fetches the blocks `concurrency` at a time and summarizes them in the order of
+/// `urls`, the first block that can't be fetched stops the whole range
+pub async fn fetch_blocks(
+    http: &HttpClient,
+    urls: &[String],
+    concurrency: usize,
+) -> Result<Vec<BlockSummary>, Box<dyn std::error::Error>> {
+    futures::stream::iter(urls)
+        .map(|url| async move {
+            let response: BlockHeightResponse = serde_json::from_str(&http.fetch_text(url).await?)?;
+            // The main chain block comes first
+            let block = response
+                .blocks
+                .first()
+                .ok_or_else(|| format!("{url} holds no block"))?;
+
+            Ok::<_, Box<dyn std::error::Error>>(BlockSummary::from_block(block))
+        })
+        .buffered(concurrency)
+        .try_collect()
+        .await
+}
+
+/// Prints a row per block and the totals of the range, or both in JSON or YAML
+pub fn print_blocks(cli: &Cli, blocks: &[BlockSummary]) -> Result<(), Box<dyn std::error::Error>> {
+    let totals = RangeTotals::of(blocks);
+
+    if cli.format != OutputFormat::Table {
+        return print_result(
+            cli,
+            &serde_json::json!({ "blocks": blocks, "totals": totals }),
+        );
+    }
+
+    let amount = |satoshis: u64| format_btc(satoshis, cli.btc_precision, cli.round);
+    let rate = |fee_rate: Option<f64>| {
+        fee_rate
+            .map(|fee_rate| format!("{fee_rate:.1} sat/vB"))
+            .unwrap_or_else(|| "N/A".to_string())
+    };
+
+    let table: Vec<_> = blocks
+        .iter()
+        .map(|block| {
+            vec![
+                paint(
+                    Color::Blue,
+                    block.height.to_formatted_string(&number_format()),
+                )
+                .cell(),
+                paint(
+                    Color::Blue,
+                    block
+                        .time
+                        .map(|time| time.format("%Y-%m-%d %H:%M UTC").to_string())
+                        .unwrap_or_default(),
+                )
+                .cell(),
+                paint(
+                    Color::Blue,
+                    block.transactions.to_formatted_string(&number_format()),
+                )
+                .cell(),
+                amount_cell(Color::Blue, amount(block.moved_satoshis)),
+                amount_cell(Color::Blue, amount(block.fees_satoshis)),
+                amount_cell(Color::Blue, rate(block.fee_rate)),
+            ]
+        })
+        .collect();
+
+    let table = table
+        .table()
+        .title(vec![
+            "Height".cell().bold(true),
+            "Time".cell().bold(true),
+            "Transactions".cell().bold(true),
+            "BTC Moved".cell().bold(true),
+            "Fees".cell().bold(true),
+            "Avg Fee Rate".cell().bold(true),
+        ])
+        .foreground_color(Some(cli_table::Color::Blue));
+
+    if !cli.summary {
+        print_table(table)?;
+        println!();
+    }
+
+    let total_style = summary_style(Color::Blue);
+    let totals_table = vec![vec![
+        paint(total_style, totals.blocks.to_string()).cell(),
+        paint(
+            total_style,
+            totals.transactions.to_formatted_string(&number_format()),
+        )
+        .cell(),
+        amount_cell(total_style, amount(totals.moved_satoshis)),
+        amount_cell(total_style, amount(totals.fees_satoshis)),
+        amount_cell(total_style, rate(totals.fee_rate)),
+    ]]
+    .table()
+    .title(vec![
+        "Blocks".cell().bold(true),
+        "Transactions".cell().bold(true),
+        "Total BTC Moved".cell().bold(true),
+        "Total Fees".cell().bold(true),
+        "Avg Fee Rate".cell().bold(true),
+    ])
+    .foreground_color(summary_border(Color::Blue));
+
+    print_table(totals_table)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn block_summary_leaves_the_coinbase_out() {
+        let block: Block = serde_json::from_str(
+            r#"{
+                "hash": "000000abc",
+                "height": 820000,
+                "time": 1700000000,
+                "tx": [
+                    {"hash": "coinbase", "inputs": [{}], "out": [{"value": 625010000}]},
+                    {
+                        "hash": "payment",
+                        "inputs": [{"prev_out": {"value": 100000}}],
+                        "out": [{"value": 90000}],
+                        "size": 200,
+                        "weight": 400
+                    }
+                ]
+            }"#,
+        )
+        .unwrap();
+
+        let summary = BlockSummary::from_block(&block);
+        assert_eq!(summary.transactions, 2);
+        assert_eq!(summary.moved_satoshis, 90_000);
+        assert_eq!(summary.fees_satoshis, 10_000);
+        assert_eq!(summary.fee_rate, Some(100.0));
+
+        assert!(check_range(820_000, 820_099).is_ok());
+        assert!(check_range(820_000, 820_100).is_err());
+        assert!(check_range(820_001, 820_000).is_err());
+    }
+}
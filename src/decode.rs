@@ -0,0 +1,122 @@
+use bitcoin::consensus::encode::deserialize_hex;
+use bitcoin::{Address, Network};
+use serde::Serialize;
+
+use crate::Output;
+
+/// Transaction decoded from its raw hex, without asking the API anything
+#[derive(Debug, Serialize)]
+pub struct DecodedTransaction {
+    pub txid: String,
+    pub vsize: u64,
+    pub inputs: Vec<DecodedInput>,
+    pub out: Vec<Output>,
+}
+
+/// A raw transaction only knows which output each input spends, not its value
+#[derive(Debug, Serialize)]
+pub struct DecodedInput {
+    pub outpoint: String, // txid:vout of the spent output
+    pub has_witness: bool,
+}
+
+impl DecodedTransaction {
+    /// Sum of the values of the outputs, in satoshis
+    pub fn total_output(&self) -> u64 {
+        self.out.iter().map(|output| output.value).sum()
+    }
+}
+
+/// Decodes a serialized transaction, signed or not, as hex. The addresses of the
+/// outputs are written for testnet with `testnet`
+pub fn decode_raw_transaction(
+    raw_hex: &str,
+    testnet: bool,
+) -> Result<DecodedTransaction, Box<dyn std::error::Error>> {
+    let network = if testnet {
+        Network::Testnet
+    } else {
+        Network::Bitcoin
+    };
+
+    let transaction: bitcoin::Transaction = deserialize_hex(raw_hex.trim())?;
+
+    let inputs = transaction
+        .input
+        .iter()
+        .map(|input| DecodedInput {
+            outpoint: input.previous_output.to_string(),
+            has_witness: !input.witness.is_empty(),
+        })
+        .collect();
+
+    let out = transaction
+        .output
+        .iter()
+        .map(|output| Output {
+            value: output.value.to_sat(),
+            addr: Address::from_script(&output.script_pubkey, network)
+                .ok()
+                .map(|address| address.to_string()),
+        })
+        .collect();
+
+    Ok(DecodedTransaction {
+        txid: transaction.compute_txid().to_string(),
+        vsize: transaction.vsize() as u64,
+        inputs,
+        out,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The transaction of block 170, the first one between two people
+    const BLOCK_170_TX: &str = "0100000001c997a5e56e104102fa209c6a852dd90660a20b2d9c352423edce25857fcd3704000000004847304402204e45e16932b8af514961a1d3a1a25fdf3f4f7732e9d624c6c61548ab5fb8cd410220181522ec8eca07de4860a4acdd12909d831cc56cbbac4622082221a8768d1d0901ffffffff0200ca9a3b00000000434104ae1a62fe09c5f51b13905f07f06b99a2f7159b2225f374cd378d71302fa28414e7aab37397f554a7df5f142c21c1b7303b8a0626f1baded5c72a704f7e6cd84cac00286bee0000000043410411db93e1dcdb8a016b49840f8c53bc1eb68a382e97b1482ecad7b148a6909a5cb2e0eaddfb84ccf9744464f82e160bfa9b8b64f9d4c03f999b8643f656b412a3ac00000000";
+
+    #[test]
+    fn decodes_the_first_person_to_person_transaction() {
+        let decoded = decode_raw_transaction(BLOCK_170_TX, false).unwrap();
+
+        assert_eq!(
+            decoded.txid,
+            "f4184fc596403b9d638783cf57adfe4c75c605f6356fbc91338530e9831e9e16"
+        );
+        assert_eq!(decoded.inputs.len(), 1);
+        assert!(!decoded.inputs[0].has_witness);
+        assert_eq!(decoded.out.len(), 2);
+        assert_eq!(decoded.total_output(), 5_000_000_000);
+        // Pay-to-pubkey outputs have no address
+        assert_eq!(decoded.out[0].addr, None);
+    }
+
+    #[test]
+    fn output_addresses_follow_the_network() {
+        use bitcoin::absolute::LockTime;
+        use bitcoin::consensus::encode::serialize_hex;
+        use bitcoin::hashes::Hash;
+        use bitcoin::{Amount, ScriptBuf, TxOut, WPubkeyHash};
+
+        let transaction = bitcoin::Transaction {
+            version: bitcoin::transaction::Version::TWO,
+            lock_time: LockTime::ZERO,
+            input: vec![],
+            output: vec![TxOut {
+                value: Amount::from_sat(10_000),
+                script_pubkey: ScriptBuf::new_p2wpkh(&WPubkeyHash::all_zeros()),
+            }],
+        };
+        let raw_hex = serialize_hex(&transaction);
+
+        let address = |testnet| {
+            decode_raw_transaction(&raw_hex, testnet).unwrap().out[0]
+                .addr
+                .clone()
+                .unwrap()
+        };
+        assert!(address(false).starts_with("bc1q"));
+        assert!(address(true).starts_with("tb1q"));
+    }
+}
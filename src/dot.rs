@@ -0,0 +1,118 @@
+use std::fmt::Write;
+
+use crate::format::{compact_hash, format_btc, RoundingMode};
+use crate::Transaction;
+
+/// Renders the transactions as a Graphviz graph, to pipe into `dot -Tpng`: every
+/// transaction is a box fed by its input addresses and paying its output addresses,
+/// the edges labelled with the amounts. An address found in several transactions
+/// is a single node, so the graph of a batch shows how they are linked.
+pub fn transactions_to_dot(
+    transactions: &[Transaction],
+    btc_precision: u32,
+    round: RoundingMode,
+) -> String {
+    let amount = |value: u64| format_btc(value, btc_precision, round);
+
+    let mut dot =
+        String::from("digraph transactions {\n    rankdir=LR;\n    node [shape=ellipse];\n");
+
+    for transaction in transactions {
+        let tx_node = format!("tx:{}", transaction.hash);
+        let _ = writeln!(
+            dot,
+            "    \"{tx_node}\" [shape=box, label=\"{}\"];",
+            compact_hash(&transaction.hash)
+        );
+
+        if transaction.is_coinbase() {
+            let coinbase_node = format!("coinbase:{}", transaction.hash);
+            let _ = writeln!(
+                dot,
+                "    \"{coinbase_node}\" [shape=plaintext, label=\"coinbase\"];"
+            );
+            let _ = writeln!(
+                dot,
+                "    \"{coinbase_node}\" -> \"{tx_node}\" [label=\"{}\"];",
+                amount(transaction.total_output())
+            );
+        }
+
+        for prev_out in transaction
+            .inputs
+            .iter()
+            .filter_map(|input| input.prev_out.as_ref())
+        {
+            let _ = writeln!(
+                dot,
+                "    \"{}\" -> \"{tx_node}\" [label=\"{}\"];",
+                address_node(&prev_out.addr, &transaction.hash, "input"),
+                amount(prev_out.value)
+            );
+        }
+
+        for (index, output) in transaction.out.iter().enumerate() {
+            let node = address_node(&output.addr, &transaction.hash, &index.to_string());
+            if output.addr.is_none() {
+                let _ = writeln!(
+                    dot,
+                    "    \"{node}\" [shape=plaintext, label=\"non-standard\"];"
+                );
+            }
+            let _ = writeln!(
+                dot,
+                "    \"{tx_node}\" -> \"{node}\" [label=\"{}\"];",
+                amount(output.value)
+            );
+        }
+
+        if let Some(fee) = transaction
+            .fee()
+            .filter(|&fee| fee > 0 && !transaction.is_coinbase())
+        {
+            let fee_node = format!("fee:{}", transaction.hash);
+            let _ = writeln!(dot, "    \"{fee_node}\" [shape=plaintext, label=\"fee\"];");
+            let _ = writeln!(
+                dot,
+                "    \"{tx_node}\" -> \"{fee_node}\" [style=dashed, label=\"{}\"];",
+                amount(fee)
+            );
+        }
+    }
+
+    dot.push_str("}\n");
+    dot
+}
+
+/// Node of an address, the scripts without one get a node of their own
+fn address_node(addr: &Option<String>, hash: &str, position: &str) -> String {
+    match addr {
+        Some(addr) => addr.clone(),
+        None => format!("script:{hash}:{position}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn links_the_inputs_to_the_outputs_through_the_transaction() {
+        let transaction: Transaction = serde_json::from_str(
+            r#"{
+                "hash": "abc",
+                "inputs": [{"prev_out": {"addr": "bc1qsender", "value": 100000}}],
+                "out": [{"addr": "bc1qreceiver", "value": 90000}, {"value": 0}]
+            }"#,
+        )
+        .unwrap();
+
+        let dot = transactions_to_dot(&[transaction], 8, RoundingMode::HalfUp);
+
+        assert!(dot.starts_with("digraph transactions {"));
+        assert!(dot.contains("\"bc1qsender\" -> \"tx:abc\" [label=\"0.001 BTC\"];"));
+        assert!(dot.contains("\"tx:abc\" -> \"bc1qreceiver\" [label=\"0.0009 BTC\"];"));
+        assert!(dot.contains("\"tx:abc\" -> \"script:abc:1\""));
+        assert!(dot.contains("\"tx:abc\" -> \"fee:abc\" [style=dashed, label=\"0.0001 BTC\"];"));
+    }
+}
@@ -0,0 +1,142 @@
+use serde::Deserialize;
+
+use crate::http::HttpClient;
+use crate::{MAINNET_API_BASE, TESTNET_API_BASE};
+
+/// Fee rates, in sat/vByte, currently recommended by the API for new transactions
+#[derive(Debug, Deserialize)]
+pub struct FeeEstimates {
+    pub regular: f64,
+    pub priority: f64,
+    pub limits: FeeLimits,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FeeLimits {
+    pub min: f64,
+}
+
+/// Average time between two blocks
+pub const BLOCK_INTERVAL_MINUTES: u64 = 10;
+
+/// Blocks a transaction paying the priority rate waits, it gets in the next one
+const PRIORITY_BLOCKS: f64 = 1.0;
+
+/// Blocks a transaction paying the regular rate waits, a few
+const REGULAR_BLOCKS: f64 = 3.0;
+
+/// Blocks a transaction paying the minimum rate waits for the mempool to clear, about a day
+const MINIMUM_BLOCKS: f64 = 144.0;
+
+/// Fee paid per virtual byte of the transaction
+pub fn fee_rate(fee: u64, vsize: u64) -> Option<f64> {
+    if vsize == 0 {
+        return None;
+    }
+
+    Some(fee as f64 / vsize as f64)
+}
+
+/// Plain words guidance on how soon a transaction paying `fee_rate` sat/vByte
+/// should confirm, compared with the current mempool rates
+pub fn fee_verdict(fee_rate: f64, estimates: &FeeEstimates) -> &'static str {
+    if fee_rate >= estimates.priority {
+        "at or above the priority rate — likely to confirm in the next block"
+    } else if fee_rate >= estimates.regular {
+        "above median — should confirm within the next few blocks"
+    } else if fee_rate >= estimates.limits.min {
+        "below median — may wait until the mempool clears"
+    } else {
+        "below the minimum rate — may never confirm"
+    }
+}
+
+/// Rough number of blocks a transaction paying `fee_rate` sat/vByte waits before
+/// confirming, interpolated between the blocks each mempool rate waits. `None`
+/// below the minimum rate, it may never confirm
+pub fn confirmation_blocks(fee_rate: f64, estimates: &FeeEstimates) -> Option<u64> {
+    let blocks = if fee_rate >= estimates.priority {
+        PRIORITY_BLOCKS
+    } else if fee_rate >= estimates.regular {
+        interpolate(
+            fee_rate,
+            (estimates.regular, REGULAR_BLOCKS),
+            (estimates.priority, PRIORITY_BLOCKS),
+        )
+    } else if fee_rate >= estimates.limits.min {
+        interpolate(
+            fee_rate,
+            (estimates.limits.min, MINIMUM_BLOCKS),
+            (estimates.regular, REGULAR_BLOCKS),
+        )
+    } else {
+        return None;
+    };
+
+    Some(blocks.round() as u64)
+}
+
+/// Blocks waited at `fee_rate`, on the line between two (rate, blocks) points
+fn interpolate(
+    fee_rate: f64,
+    (low_rate, low_blocks): (f64, f64),
+    (high_rate, high_blocks): (f64, f64),
+) -> f64 {
+    if high_rate <= low_rate {
+        return high_blocks;
+    }
+
+    low_blocks + (fee_rate - low_rate) / (high_rate - low_rate) * (high_blocks - low_blocks)
+}
+
+/// The fee endpoint lives in the `api.` subdomain of blockchain.info, other
+/// explorers are expected to serve it under their base URL. blockchain.info has
+/// no estimates for testnet, `None`
+pub fn fee_estimates_url(api_base: &str) -> Option<String> {
+    match api_base {
+        MAINNET_API_BASE => Some("https://api.blockchain.info/mempool/fees".to_string()),
+        TESTNET_API_BASE => None,
+        api_base => Some(format!("{api_base}/mempool/fees")),
+    }
+}
+
+pub async fn fetch_fee_estimates(
+    http: &HttpClient,
+    fee_estimates_url: &str,
+) -> Result<FeeEstimates, Box<dyn std::error::Error>> {
+    let fee_estimates_json = http.fetch_text(fee_estimates_url).await?;
+
+    Ok(serde_json::from_str(&fee_estimates_json)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn confirmation_blocks_follow_the_mempool_rates() {
+        let estimates: FeeEstimates = serde_json::from_str(
+            r#"{"regular": 10, "priority": 20, "limits": {"min": 2, "max": 100}}"#,
+        )
+        .unwrap();
+
+        assert_eq!(confirmation_blocks(25.0, &estimates), Some(1));
+        assert_eq!(confirmation_blocks(15.0, &estimates), Some(2));
+        assert_eq!(confirmation_blocks(10.0, &estimates), Some(3));
+        assert_eq!(confirmation_blocks(2.0, &estimates), Some(144));
+        assert_eq!(confirmation_blocks(1.0, &estimates), None);
+    }
+
+    #[test]
+    fn testnet_has_no_fee_estimates() {
+        assert_eq!(
+            fee_estimates_url(MAINNET_API_BASE).as_deref(),
+            Some("https://api.blockchain.info/mempool/fees")
+        );
+        assert_eq!(fee_estimates_url(TESTNET_API_BASE), None);
+        assert_eq!(
+            fee_estimates_url("http://localhost:3000").as_deref(),
+            Some("http://localhost:3000/mempool/fees")
+        );
+    }
+}
@@ -0,0 +1,149 @@
+use std::str::FromStr;
+
+use serde_json::{Map, Value};
+
+use crate::SATOSHIS_PER_BTC;
+
+/// A field kept in the JSON output by --fields, written under `name`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldSelection {
+    pub field: String,
+    pub name: String,
+}
+
+/// `field` keeps the field as is, `field:name` renames it
+impl FromStr for FieldSelection {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<FieldSelection, String> {
+        let (field, name) = value.split_once(':').unwrap_or((value, value));
+        let (field, name) = (field.trim(), name.trim());
+
+        if field.is_empty() || name.is_empty() {
+            return Err(format!(
+                "{value} isn't a valid field, use <field> or <field>:<name>"
+            ));
+        }
+
+        Ok(FieldSelection {
+            field: field.to_string(),
+            name: name.to_string(),
+        })
+    }
+}
+
+/// Keeps only the selected fields of the JSON objects, in the order they were given.
+///
+/// The objects holding at least one of the fields are reduced to them, the others
+/// (i.e the `{"transactions", "summary"}` wrapper of a batch) and the arrays are
+/// walked so the selection applies to the items they hold. No selection leaves the
+/// value untouched.
+pub fn select_fields(value: Value, fields: &[FieldSelection]) -> Value {
+    if fields.is_empty() {
+        return value;
+    }
+
+    match value {
+        Value::Array(items) => Value::Array(
+            items
+                .into_iter()
+                .map(|item| select_fields(item, fields))
+                .collect(),
+        ),
+        Value::Object(mut object)
+            if fields
+                .iter()
+                .any(|selection| object.contains_key(&selection.field)) =>
+        {
+            let mut selected = Map::new();
+            for selection in fields {
+                if let Some(field) = object.remove(&selection.field) {
+                    selected.insert(selection.name.clone(), field);
+                }
+            }
+
+            Value::Object(selected)
+        }
+        Value::Object(object) => Value::Object(
+            object
+                .into_iter()
+                .map(|(key, item)| (key, select_fields(item, fields)))
+                .collect(),
+        ),
+        value => value,
+    }
+}
+
+/// Writes every amount twice, as the exact integer of satoshis and as a BTC float:
+/// `value_satoshis` and `value_btc` for the inputs and outputs and `amount_btc`
+/// next to the `amount_satoshis` of the unconfirmed transactions
+pub fn include_raw_values(value: Value) -> Value {
+    match value {
+        Value::Array(items) => Value::Array(items.into_iter().map(include_raw_values).collect()),
+        Value::Object(object) => {
+            let mut object: Map<String, Value> = object
+                .into_iter()
+                .map(|(key, item)| (key, include_raw_values(item)))
+                .collect();
+
+            if let Some(satoshis) = object.get("value").and_then(Value::as_u64) {
+                object.insert("value_satoshis".to_string(), satoshis.into());
+                object.insert("value_btc".to_string(), btc_float(satoshis));
+            }
+            if let Some(satoshis) = object.get("amount_satoshis").and_then(Value::as_u64) {
+                object.insert("amount_btc".to_string(), btc_float(satoshis));
+            }
+
+            Value::Object(object)
+        }
+        value => value,
+    }
+}
+
+/// Amount of satoshis in BTC, as a JSON number
+fn btc_float(satoshis: u64) -> Value {
+    (satoshis as f64 / SATOSHIS_PER_BTC as f64).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn keeps_and_renames_the_selected_fields_in_order() {
+        let fields: Vec<FieldSelection> = ["time", "hash:txid"]
+            .iter()
+            .map(|field| field.parse().unwrap())
+            .collect();
+
+        let batch = json!({
+            "transactions": [{"hash": "abc", "size": 250, "time": 1700000000}],
+            "summary": {"succeeded": 1},
+        });
+
+        let selected = select_fields(batch, &fields);
+        assert_eq!(
+            serde_json::to_string(&selected["transactions"]).unwrap(),
+            r#"[{"time":1700000000,"txid":"abc"}]"#
+        );
+        assert_eq!(selected["summary"], json!({"succeeded": 1}));
+
+        assert!("hash:".parse::<FieldSelection>().is_err());
+    }
+
+    #[test]
+    fn raw_values_sit_next_to_the_btc_amounts() {
+        let transaction = json!({
+            "hash": "abc",
+            "out": [{"value": 150_000, "addr": "bc1qreceiver"}],
+        });
+
+        let output = &include_raw_values(transaction)["out"][0];
+        assert_eq!(output["value_satoshis"], json!(150_000));
+        assert_eq!(output["value_btc"], json!(0.0015));
+
+        let unconfirmed = include_raw_values(json!([{"amount_satoshis": 100_000_000}]));
+        assert_eq!(unconfirmed[0]["amount_btc"], json!(1.0));
+    }
+}
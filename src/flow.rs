@@ -0,0 +1,183 @@
+use ansi_term::Color;
+use num_format::ToFormattedString;
+
+use crate::format::{abbreviate, format_btc};
+use crate::output::{number_format, paint};
+use crate::{row_limit, Cli, Transaction};
+
+/// One side of a line of the diagram, an address and its amount
+struct FlowEntry {
+    label: String,
+    amount: String,
+}
+
+/// Draws the money flow of the transaction, the inputs on the left joined by a
+/// line to the outputs and the fee on the right:
+///
+/// ```text
+/// bc1qar0s…zzwf5mdq 0.015 BTC ─┬─▶ 1A1zP1eP…v7DivfNa     0.01 BTC
+///                              ├─▶ bc1qar0s…zzwf5mdq 0.004774 BTC
+///                              └─▶ fee               0.000226 BTC
+/// ```
+pub fn print_flow(cli: &Cli, transaction: &Transaction) {
+    let amount = |value| format_btc(value, cli.btc_precision, cli.round);
+    // The addresses are always shortened to keep the diagram narrow
+    let keep = match cli.abbrev_addresses {
+        0 => 8,
+        keep => keep,
+    };
+    let label = |addr: &Option<String>| {
+        addr.as_deref()
+            .map(|addr| abbreviate(addr, keep))
+            .unwrap_or_else(|| "(non-standard)".to_string())
+    };
+
+    let inputs: Vec<FlowEntry> = if transaction.is_coinbase() {
+        vec![FlowEntry {
+            label: "coinbase (new coins)".to_string(),
+            amount: amount(transaction.total_output()),
+        }]
+    } else {
+        transaction
+            .inputs
+            .iter()
+            .filter_map(|input| input.prev_out.as_ref())
+            .map(|prev_out| FlowEntry {
+                label: label(&prev_out.addr),
+                amount: amount(prev_out.value),
+            })
+            .collect()
+    };
+
+    let mut outputs: Vec<FlowEntry> = transaction
+        .out
+        .iter()
+        .map(|output| FlowEntry {
+            label: label(&output.addr),
+            amount: amount(output.value),
+        })
+        .collect();
+
+    let max_rows = row_limit(cli.max_rows_per_table);
+    let inputs = truncate(inputs, max_rows);
+    outputs = truncate(outputs, max_rows);
+
+    if let Some(fee) = transaction.fee().filter(|_| !transaction.is_coinbase()) {
+        outputs.push(FlowEntry {
+            label: "fee".to_string(),
+            amount: amount(fee),
+        });
+    }
+
+    let width = |entries: &[FlowEntry]| {
+        entries
+            .iter()
+            .map(|entry| entry.label.chars().count())
+            .max()
+            .unwrap_or(0)
+    };
+    let (input_width, output_width) = (width(&inputs), width(&outputs));
+    let amount_width = |entries: &[FlowEntry]| {
+        entries
+            .iter()
+            .map(|entry| entry.amount.chars().count())
+            .max()
+            .unwrap_or(0)
+    };
+    let (input_amount_width, output_amount_width) = (amount_width(&inputs), amount_width(&outputs));
+
+    let lines = inputs.len().max(outputs.len());
+    for line in 0..lines {
+        let input = inputs.get(line);
+        let output = outputs.get(line);
+
+        let left = match input {
+            Some(input) => format!(
+                "{} {} {}",
+                paint(Color::Cyan, format!("{:<input_width$}", input.label)),
+                paint(
+                    Color::Green,
+                    format!("{:>input_amount_width$}", input.amount)
+                ),
+                paint(Color::Purple, "─")
+            ),
+            None => " ".repeat(input_width + input_amount_width + 3),
+        };
+
+        let right = match output {
+            Some(output) => format!(
+                "{} {} {}",
+                paint(Color::Purple, "─▶"),
+                paint(Color::Cyan, format!("{:<output_width$}", output.label)),
+                paint(
+                    Color::Green,
+                    format!("{:>output_amount_width$}", output.amount)
+                )
+            ),
+            None => String::new(),
+        };
+
+        let junction = junction(line, lines, input.is_some(), output.is_some());
+        println!("{left}{}{right}", paint(Color::Purple, junction));
+    }
+    println!();
+}
+
+/// Keeps the first `max_rows` entries, the last one telling how many were left out
+fn truncate(mut entries: Vec<FlowEntry>, max_rows: usize) -> Vec<FlowEntry> {
+    if entries.len() > max_rows {
+        let hidden = entries.len() - max_rows;
+        entries.truncate(max_rows);
+        entries.push(FlowEntry {
+            label: format!(
+                "... and {} more",
+                hidden.to_formatted_string(&number_format())
+            ),
+            amount: String::new(),
+        });
+    }
+
+    entries
+}
+
+/// Character of the vertical line joining the inputs to the outputs at `line`,
+/// depending on which sides have an entry on it
+fn junction(line: usize, lines: usize, input: bool, output: bool) -> char {
+    let first = line == 0;
+    let last = line + 1 == lines;
+
+    match (first, last, input, output) {
+        (true, true, _, _) => '─',
+        (true, false, true, true) => '┬',
+        (true, false, true, false) => '┐',
+        (true, false, false, _) => '┌',
+        (false, true, true, true) => '┴',
+        (false, true, true, false) => '┘',
+        (false, true, false, _) => '└',
+        (false, false, true, true) => '┼',
+        (false, false, true, false) => '┤',
+        (false, false, false, _) => '├',
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn junction_joins_the_inputs_to_the_outputs() {
+        // One input paying two outputs and the fee
+        let column: String = (0..3)
+            .map(|line| junction(line, 3, line < 1, true))
+            .collect();
+        assert_eq!(column, "┬├└");
+
+        // Three inputs merged into one output
+        let column: String = (0..3)
+            .map(|line| junction(line, 3, true, line < 1))
+            .collect();
+        assert_eq!(column, "┬┤┘");
+
+        assert_eq!(junction(0, 1, true, true), '─');
+    }
+}
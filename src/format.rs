@@ -0,0 +1,254 @@
+use clap::ValueEnum;
+use num_format::{Format, ToFormattedString};
+use rust_decimal::{Decimal, RoundingStrategy};
+
+use crate::output::{options, SymbolPosition};
+
+/// Formats a fiat amount with its currency symbol, the thousands and decimal
+/// separators of `locale` and up to `decimals` decimal places, the zeros past the
+/// cents are trimmed. The symbol goes on the side of the amount --locale writes it.
+///
+/// e.g `format_fiat(1234.5, "USD", &Locale::en, 5)` gives `$1,234.50`, and
+/// `format_fiat(1234.5, "EUR", &Locale::de, 2)` gives `1.234,50 €` with `--locale de`
+pub fn format_fiat(amount: f64, currency: &str, locale: &impl Format, decimals: usize) -> String {
+    // Round first, so the carry of the fraction reaches the integer part (0.999 -> 1.00)
+    let rounded = format!("{:.*}", decimals, amount.abs());
+    let (integer, fraction) = rounded.split_once('.').unwrap_or((&rounded, ""));
+    let fraction = trim_fraction(fraction, CENTS_DECIMALS);
+
+    let integer = integer
+        .parse::<u64>()
+        .unwrap_or(u64::MAX)
+        .to_formatted_string(locale);
+
+    let is_negative = amount < 0.0 && rounded.chars().any(|c| c.is_ascii_digit() && c != '0');
+    let sign = if is_negative { "-" } else { "" };
+
+    let mut number = integer;
+    if !fraction.is_empty() {
+        number.push_str(locale.decimal().into_str());
+        number.push_str(fraction);
+    }
+
+    place_symbol(sign, &number, currency, options().symbol_position)
+}
+
+/// Writes the currency symbol on its side of an amount (`-€1,234.56` or `-1.234,56 €`)
+fn place_symbol(sign: &str, number: &str, currency: &str, position: SymbolPosition) -> String {
+    let symbol = currency_symbol(currency);
+    match position {
+        SymbolPosition::Before => format!("{sign}{symbol}{number}"),
+        SymbolPosition::After => format!("{sign}{number} {}", symbol.trim_end()),
+    }
+}
+
+/// Decimals a fiat amount keeps even when they are zeros, the cents
+const CENTS_DECIMALS: usize = 2;
+
+/// Drops the trailing zeros of a fraction, keeping at least `keep` digits
+fn trim_fraction(fraction: &str, keep: usize) -> &str {
+    let significant = fraction.trim_end_matches('0').len();
+    &fraction[..significant.max(keep.min(fraction.len()))]
+}
+
+/// Symbol shown in front of the amounts of a currency, unknown currencies use their code
+fn currency_symbol(currency: &str) -> String {
+    match currency.to_uppercase().as_str() {
+        "USD" => "$".to_string(),
+        "EUR" => "€".to_string(),
+        "GBP" => "£".to_string(),
+        "JPY" => "¥".to_string(),
+        code => format!("{code} "),
+    }
+}
+
+/// How the BTC amounts are rounded when they are shown with less than 8 decimals
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum RoundingMode {
+    HalfUp,
+    Truncate,
+    Ceil,
+}
+
+impl From<RoundingMode> for RoundingStrategy {
+    fn from(mode: RoundingMode) -> RoundingStrategy {
+        match mode {
+            RoundingMode::HalfUp => RoundingStrategy::MidpointAwayFromZero,
+            RoundingMode::Truncate => RoundingStrategy::ToZero,
+            RoundingMode::Ceil => RoundingStrategy::ToPositiveInfinity,
+        }
+    }
+}
+
+/// Converts satoshis to BTC without going through floating point, rounded to
+/// `decimals` decimal places and without trailing zeros
+///
+/// e.g `format_btc(150_000, 8, RoundingMode::HalfUp)` gives `0.0015 BTC`
+pub fn format_btc(satoshis: impl Into<i128>, decimals: u32, rounding: RoundingMode) -> String {
+    let btc = btc_amount(satoshis)
+        .round_dp_with_strategy(decimals, rounding.into())
+        .normalize();
+
+    let options = options();
+    format!(
+        "{} BTC",
+        localize_number(
+            &btc.to_string(),
+            &options.btc_thousands_separator,
+            &options.decimal_separator
+        )
+    )
+}
+
+/// Rewrites a number written with a `.` and no grouping (i.e `-1234.5`) with the
+/// given thousands and decimal separators
+pub fn localize_number(
+    neutral: &str,
+    thousands_separator: &str,
+    decimal_separator: &str,
+) -> String {
+    let (sign, unsigned) = match neutral.strip_prefix('-') {
+        Some(unsigned) => ("-", unsigned),
+        None => ("", neutral),
+    };
+    let (integer, fraction) = match unsigned.split_once('.') {
+        Some((integer, fraction)) => (integer, Some(fraction)),
+        None => (unsigned, None),
+    };
+
+    // Groups of three digits, counted from the right
+    let mut grouped = String::new();
+    for (i, digit) in integer.chars().enumerate() {
+        if i > 0 && (integer.len() - i) % 3 == 0 {
+            grouped.push_str(thousands_separator);
+        }
+        grouped.push(digit);
+    }
+
+    match fraction {
+        Some(fraction) => format!("{sign}{grouped}{decimal_separator}{fraction}"),
+        None => format!("{sign}{grouped}"),
+    }
+}
+
+/// Exact amount of BTC of some satoshis, always with 8 decimals (i.e `0.00150000`)
+pub fn btc_amount(satoshis: impl Into<i128>) -> Decimal {
+    Decimal::from_i128_with_scale(satoshis.into(), 8)
+}
+
+/// Shortens a transaction hash to its first and last 8 characters, i.e `abcd1234…ef567890`
+pub fn compact_hash(hash: &str) -> String {
+    abbreviate(hash, 8)
+}
+
+/// Keeps the first and last `keep` characters of a hash or an address around an
+/// ellipsis, a `keep` of 0 or a text too short to gain anything leaves it whole
+pub fn abbreviate(text: &str, keep: usize) -> String {
+    if keep == 0 || text.len() <= keep * 2 + 1 || !text.is_ascii() {
+        return text.to_string();
+    }
+
+    format!("{}…{}", &text[..keep], &text[text.len() - keep..])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use num_format::Locale;
+
+    #[test]
+    fn btc_edge_cases() {
+        assert_eq!(format_btc(0u64, 8, RoundingMode::HalfUp), "0 BTC");
+        assert_eq!(format_btc(1u64, 8, RoundingMode::HalfUp), "0.00000001 BTC");
+        assert_eq!(
+            format_btc(u64::MAX, 8, RoundingMode::HalfUp),
+            "184467440737.09551615 BTC"
+        );
+        assert_eq!(
+            format_btc(-150_000i64, 8, RoundingMode::HalfUp),
+            "-0.0015 BTC"
+        );
+    }
+
+    #[test]
+    fn localizes_the_separators() {
+        assert_eq!(localize_number("-1234567.5", " ", ","), "-1 234 567,5");
+        assert_eq!(localize_number("123", ".", ","), "123");
+        assert_eq!(localize_number("0.00015", "", "."), "0.00015");
+    }
+
+    #[test]
+    fn btc_rounding_modes() {
+        assert_eq!(format_btc(1u64, 2, RoundingMode::HalfUp), "0 BTC");
+        assert_eq!(format_btc(1u64, 2, RoundingMode::Truncate), "0 BTC");
+        assert_eq!(format_btc(1u64, 2, RoundingMode::Ceil), "0.01 BTC");
+        assert_eq!(format_btc(150_000_000u64, 0, RoundingMode::HalfUp), "2 BTC");
+        assert_eq!(
+            format_btc(150_000_000u64, 0, RoundingMode::Truncate),
+            "1 BTC"
+        );
+    }
+
+    #[test]
+    fn fiat_under_one_dollar() {
+        assert_eq!(format_fiat(0.5, "USD", &Locale::en, 2), "$0.50");
+        assert_eq!(format_fiat(0.0, "USD", &Locale::en, 2), "$0.00");
+        assert_eq!(format_fiat(0.999, "USD", &Locale::en, 2), "$1.00");
+        // Rounded to zero, so there is nothing left to be negative
+        assert_eq!(format_fiat(-0.004, "USD", &Locale::en, 2), "$0.00");
+    }
+
+    #[test]
+    fn fiat_extra_decimals_drop_their_trailing_zeros() {
+        assert_eq!(format_fiat(1234.0, "USD", &Locale::en, 5), "$1,234.00");
+        assert_eq!(format_fiat(1234.5, "USD", &Locale::en, 5), "$1,234.50");
+        assert_eq!(format_fiat(12.34, "USD", &Locale::en, 5), "$12.34");
+        assert_eq!(format_fiat(0.01234, "USD", &Locale::en, 5), "$0.01234");
+        assert_eq!(format_fiat(0.5, "USD", &Locale::en, 1), "$0.5");
+        assert_eq!(format_fiat(7.0, "USD", &Locale::en, 0), "$7");
+    }
+
+    #[test]
+    fn fiat_separators_follow_the_locale() {
+        assert_eq!(
+            format_fiat(1_234_567.891, "USD", &Locale::en, 2),
+            "$1,234,567.89"
+        );
+        assert_eq!(format_fiat(-1_500.0, "CHF", &Locale::en, 0), "-CHF 1,500");
+    }
+
+    #[test]
+    fn fiat_symbol_goes_on_the_side_of_the_locale() {
+        assert_eq!(
+            place_symbol("", "1.234,56", "EUR", SymbolPosition::After),
+            "1.234,56 €"
+        );
+        assert_eq!(
+            place_symbol("-", "1.500", "CHF", SymbolPosition::After),
+            "-1.500 CHF"
+        );
+        assert_eq!(
+            place_symbol("-", "1,234.50", "EUR", SymbolPosition::Before),
+            "-€1,234.50"
+        );
+        assert_eq!(
+            place_symbol("", "1'500", "CHF", SymbolPosition::Before),
+            "CHF 1'500"
+        );
+    }
+
+    #[test]
+    fn compact_hash_keeps_both_ends() {
+        let hash = "136937e5a742645ce873f079f8668aefdc2d06b8172e903d031a8bfb48969450";
+        assert_eq!(compact_hash(hash), "136937e5…48969450");
+        assert_eq!(compact_hash("short"), "short");
+    }
+
+    #[test]
+    fn abbreviate_keeps_the_requested_ends() {
+        let address = "bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdq";
+        assert_eq!(abbreviate(address, 6), "bc1qar…wf5mdq");
+        assert_eq!(abbreviate(address, 0), address);
+        assert_eq!(abbreviate("1abc2", 2), "1abc2");
+    }
+}
@@ -0,0 +1,220 @@
+use ansi_term::Color;
+use chrono::{DateTime, Utc};
+use cli_table::{Cell, Style, Table};
+use serde::Serialize;
+
+use crate::fields::select_fields;
+use crate::format::format_btc;
+use crate::http::HttpClient;
+use crate::output::{amount_cell, paint, print_table};
+use crate::{
+    shown_hash, write_html, write_messagepack, AddressTransaction, BitcoinAddress, Cli,
+    OutputFormat,
+};
+
+/// Transactions requested per page, the most the API returns at once
+const PAGE_SIZE: usize = 50;
+
+/// One transaction of the history, the amounts are in satoshis
+#[derive(Debug, Serialize)]
+struct HistoryRow<'a> {
+    hash: &'a str,
+    time: Option<DateTime<Utc>>,
+    block_height: Option<u64>,
+    received: u64,
+    sent: u64,
+}
+
+/// Prints the whole transaction history of the address, newest first.
+///
+/// The history is fetched one page at a time and every page is printed before the
+/// next one is requested, so even an address with tens of thousands of
+/// transactions never has more than a page in memory. In JSON and YAML the
+/// address and its history are written as a single document, streamed the same way,
+/// MessagePack and HTML need the whole history before writing it.
+///
+/// With --since-block the paging stops at the first transaction confirmed before
+/// the block, the older ones all come after it, and -n caps the transactions shown.
+pub async fn print_history(
+    cli: &Cli,
+    http: &HttpClient,
+    inspect_address_url: &str,
+    bitcoin_address: &BitcoinAddress,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let address = &bitcoin_address.address;
+
+    let address_value = select_fields(serde_json::to_value(bitcoin_address)?, &cli.fields);
+    match cli.format {
+        OutputFormat::Json => print!("{{\"address\":{address_value},\"history\":["),
+        OutputFormat::Yaml => print!(
+            "address:\n{}history:",
+            indent(&serde_yaml::to_string(&address_value)?)
+        ),
+        OutputFormat::Table
+        | OutputFormat::Dot
+        | OutputFormat::Messagepack
+        | OutputFormat::Html => {}
+    }
+
+    // MessagePack and HTML can't be streamed, they're written at the end
+    let mut collected = Vec::new();
+    let mut offset = 0;
+    let mut shown = 0;
+    let mut remaining = cli.number_outputs.unwrap_or(usize::MAX);
+    loop {
+        let page_url = format!("{inspect_address_url}{address}?limit={PAGE_SIZE}&offset={offset}");
+        let page: BitcoinAddress = serde_json::from_str(&http.fetch_text(&page_url).await?)?;
+        if page.txs.is_empty() {
+            break;
+        }
+
+        let is_recent = |tx: &&AddressTransaction| match (tx.block_height, cli.since_block) {
+            (Some(height), Some(since_block)) => height >= since_block,
+            _ => true,
+        };
+        let reached_older = !page.txs.iter().all(|tx| is_recent(&tx));
+
+        let rows: Vec<HistoryRow> = page
+            .txs
+            .iter()
+            .filter(is_recent)
+            .take(remaining)
+            .map(|tx| history_row(tx, address))
+            .collect();
+
+        match cli.format {
+            OutputFormat::Json => {
+                for (i, row) in rows.iter().enumerate() {
+                    let separator = if shown + i == 0 { "" } else { "," };
+                    let row = select_fields(serde_json::to_value(row)?, &cli.fields);
+                    print!("{separator}{row}");
+                }
+            }
+            OutputFormat::Yaml => {
+                if shown == 0 && !rows.is_empty() {
+                    println!();
+                }
+                for row in &rows {
+                    let row = select_fields(serde_json::to_value(row)?, &cli.fields);
+                    print!("{}", serde_yaml::to_string(&[row])?);
+                }
+            }
+            OutputFormat::Messagepack | OutputFormat::Html => {
+                for row in &rows {
+                    collected.push(select_fields(serde_json::to_value(row)?, &cli.fields));
+                }
+            }
+            OutputFormat::Table | OutputFormat::Dot if !rows.is_empty() => {
+                print_page(cli, &rows, shown, page.n_tx)?
+            }
+            OutputFormat::Table | OutputFormat::Dot => {}
+        }
+
+        offset += page.txs.len();
+        shown += rows.len();
+        remaining -= rows.len();
+        if offset as u64 >= page.n_tx || reached_older || remaining == 0 {
+            break;
+        }
+    }
+
+    match cli.format {
+        OutputFormat::Json => println!("]}}"),
+        OutputFormat::Yaml if shown == 0 => println!(" []"),
+        OutputFormat::Messagepack => write_messagepack(
+            &serde_json::json!({ "address": address_value, "history": collected }),
+        )?,
+        OutputFormat::Html => write_html(
+            cli,
+            &serde_json::json!({ "address": address_value, "history": collected }),
+        ),
+        _ => {}
+    }
+
+    Ok(())
+}
+
+/// Indents every line of a YAML document so it nests under a key
+fn indent(yaml: &str) -> String {
+    yaml.lines().map(|line| format!("  {line}\n")).collect()
+}
+
+/// What one transaction of the history did to the address
+fn history_row<'a>(tx: &'a AddressTransaction, address: &str) -> HistoryRow<'a> {
+    let (received, sent) = tx.flow(address);
+
+    HistoryRow {
+        hash: &tx.hash,
+        time: DateTime::from_timestamp(tx.time, 0),
+        block_height: tx.block_height,
+        received,
+        sent,
+    }
+}
+
+/// Prints one page of the history as a table titled with its position in the history
+fn print_page(
+    cli: &Cli,
+    rows: &[HistoryRow],
+    shown: usize,
+    n_tx: u64,
+) -> Result<(), Box<dyn std::error::Error>> {
+    println!(
+        "{}",
+        paint(
+            Color::Purple.bold(),
+            format!(
+                "Transactions {} to {} of {n_tx}",
+                shown + 1,
+                shown + rows.len()
+            )
+        )
+    );
+
+    let table: Vec<_> = rows
+        .iter()
+        .map(|row| {
+            let hash = shown_hash(cli, row.hash);
+            let time = row
+                .time
+                .map(|time| time.format("%Y-%m-%d %H:%M UTC").to_string())
+                .unwrap_or_default();
+
+            vec![
+                paint(Color::Cyan, hash).cell(),
+                paint(Color::Cyan, time).cell(),
+                paint(
+                    Color::Cyan,
+                    row.block_height
+                        .map(|height| height.to_string())
+                        .unwrap_or_else(|| "unconfirmed".to_string()),
+                )
+                .cell(),
+                amount_cell(
+                    Color::Cyan,
+                    format_btc(row.received, cli.btc_precision, cli.round),
+                ),
+                amount_cell(
+                    Color::Cyan,
+                    format_btc(row.sent, cli.btc_precision, cli.round),
+                ),
+            ]
+        })
+        .collect();
+
+    let table = table
+        .table()
+        .title(vec![
+            "Hash".cell().bold(true),
+            "Time".cell().bold(true),
+            "Block".cell().bold(true),
+            "Received".cell().bold(true),
+            "Sent".cell().bold(true),
+        ])
+        .foreground_color(Some(cli_table::Color::Cyan));
+
+    print_table(table)?;
+    println!();
+
+    Ok(())
+}
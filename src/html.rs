@@ -0,0 +1,111 @@
+use std::fmt::Write;
+
+use serde_json::Value;
+
+/// Inline styles of the report, the accent being the color of the mode
+const TABLE_STYLE: &str = "border-collapse: collapse; font-family: monospace; font-size: 13px";
+const CELL_STYLE: &str =
+    "border: 1px solid #d0d0d0; padding: 4px 8px; text-align: left; vertical-align: top";
+
+/// Renders the results as a self-contained HTML `<table>` with inline styles, to drop
+/// in an email or a web page. A list of objects gets a column per key, an object a
+/// row per key, and the nested values are tables of their own
+pub fn to_html(value: &Value, accent: &str) -> String {
+    let mut html = String::new();
+    write_value(&mut html, value, accent);
+    html.push('\n');
+    html
+}
+
+fn write_value(html: &mut String, value: &Value, accent: &str) {
+    match value {
+        Value::Array(items) if !items.is_empty() && items.iter().all(Value::is_object) => {
+            // Columns in the order the keys first appear
+            let mut columns: Vec<&String> = Vec::new();
+            for item in items.iter().filter_map(Value::as_object) {
+                for key in item.keys() {
+                    if !columns.contains(&key) {
+                        columns.push(key);
+                    }
+                }
+            }
+
+            let _ = write!(html, "<table style=\"{TABLE_STYLE}\"><thead><tr>");
+            for column in &columns {
+                write_header(html, column, accent);
+            }
+            html.push_str("</tr></thead><tbody>");
+            for item in items.iter().filter_map(Value::as_object) {
+                html.push_str("<tr>");
+                for column in &columns {
+                    let _ = write!(html, "<td style=\"{CELL_STYLE}\">");
+                    if let Some(value) = item.get(column.as_str()) {
+                        write_value(html, value, accent);
+                    }
+                    html.push_str("</td>");
+                }
+                html.push_str("</tr>");
+            }
+            html.push_str("</tbody></table>");
+        }
+        Value::Array(items) => {
+            let _ = write!(html, "<table style=\"{TABLE_STYLE}\"><tbody>");
+            for item in items {
+                let _ = write!(html, "<tr><td style=\"{CELL_STYLE}\">");
+                write_value(html, item, accent);
+                html.push_str("</td></tr>");
+            }
+            html.push_str("</tbody></table>");
+        }
+        Value::Object(fields) => {
+            let _ = write!(html, "<table style=\"{TABLE_STYLE}\"><tbody>");
+            for (key, value) in fields {
+                html.push_str("<tr>");
+                write_header(html, key, accent);
+                let _ = write!(html, "<td style=\"{CELL_STYLE}\">");
+                write_value(html, value, accent);
+                html.push_str("</td></tr>");
+            }
+            html.push_str("</tbody></table>");
+        }
+        Value::Null => {}
+        Value::String(text) => html.push_str(&escape(text)),
+        other => html.push_str(&escape(&other.to_string())),
+    }
+}
+
+fn write_header(html: &mut String, text: &str, accent: &str) {
+    let _ = write!(
+        html,
+        "<th style=\"{CELL_STYLE}; background: {accent}; color: #ffffff\">{}</th>",
+        escape(text)
+    );
+}
+
+/// Escapes the characters HTML gives a meaning to
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn a_list_of_objects_gets_a_column_per_key() {
+        let html = to_html(
+            &json!([{"hash": "abc", "fee": 100}, {"hash": "<def>", "note": null}]),
+            "#2e7d32",
+        );
+
+        assert!(html.starts_with("<table"));
+        assert_eq!(html.matches("<th ").count(), 3);
+        assert!(html.contains(">fee</th>"));
+        assert!(html.contains(">100</td>"));
+        assert!(html.contains("&lt;def&gt;"));
+    }
+}
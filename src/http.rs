@@ -0,0 +1,250 @@
+use std::fmt;
+use std::num::NonZeroU32;
+use std::time::Duration;
+
+use ansi_term::Color;
+use governor::{DefaultDirectRateLimiter, Quota, RateLimiter};
+use reqwest::{header::RETRY_AFTER, redirect, Client, Proxy, Response, StatusCode};
+
+use crate::output::paint;
+
+/// Number of times a rate-limited (HTTP 429) request, or one whose connection
+/// failed or timed out, is retried before giving up
+const MAX_RETRIES: u32 = 3;
+
+/// Delay before the first retry, it doubles on every following attempt
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+
+/// Number of redirects followed before a request is given up, an API that moved
+/// needs one or two
+const MAX_REDIRECTS: usize = 5;
+
+/// Why a request to the API didn't give a usable body
+#[derive(Debug)]
+pub enum FetchError {
+    /// The request couldn't be made or its body couldn't be read
+    Request(reqwest::Error),
+    /// The API kept redirecting the request past MAX_REDIRECTS
+    TooManyRedirects(String),
+    /// The API answered with an error status, i.e 404 for an unknown hash
+    Status(StatusCode),
+    /// The API answered with a page saying this IP is blocked instead of the data
+    Banned,
+}
+
+impl fmt::Display for FetchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FetchError::Request(error) => write!(f, "request failed: {error}"),
+            FetchError::TooManyRedirects(url) => write!(
+                f,
+                "{url} redirected more than {MAX_REDIRECTS} times, check --api-base"
+            ),
+            FetchError::Status(status) => write!(f, "the API answered {status}"),
+            FetchError::Banned => write!(
+                f,
+                "the API has blocked this IP for a while, wait a few minutes, slow down with --limit-rate or go through a proxy with --proxy"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for FetchError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            FetchError::Request(error) => Some(error),
+            FetchError::TooManyRedirects(_) | FetchError::Status(_) | FetchError::Banned => None,
+        }
+    }
+}
+
+impl From<reqwest::Error> for FetchError {
+    fn from(error: reqwest::Error) -> FetchError {
+        match error.url() {
+            Some(url) if error.is_redirect() => FetchError::TooManyRedirects(url.to_string()),
+            _ => FetchError::Request(error),
+        }
+    }
+}
+
+/// HTTP client shared by every request the tool makes
+pub struct HttpClient {
+    client: Client,
+    quiet: bool,
+    show_url: bool,
+    // Spaces out the requests when --limit-rate is given
+    limiter: Option<DefaultDirectRateLimiter>,
+}
+
+impl HttpClient {
+    /// Builds the client, `timeout` limits the time spent on each request,
+    /// `show_url` prints every requested URL on stderr, `limit_rate` caps the
+    /// number of requests per second and `proxy` is the URL of the proxy every
+    /// request goes through
+    pub fn new(
+        timeout: Option<Duration>,
+        quiet: bool,
+        show_url: bool,
+        limit_rate: Option<NonZeroU32>,
+        proxy: Option<&str>,
+    ) -> Result<HttpClient, reqwest::Error> {
+        let mut builder = Client::builder().redirect(redirect::Policy::limited(MAX_REDIRECTS));
+        if let Some(timeout) = timeout {
+            builder = builder.timeout(timeout);
+        }
+        if let Some(proxy) = proxy {
+            builder = builder.proxy(Proxy::all(proxy)?);
+        }
+
+        Ok(HttpClient {
+            client: builder.build()?,
+            quiet,
+            show_url,
+            // Without bursts, so the requests are evenly spaced
+            limiter: limit_rate.map(|limit_rate| {
+                RateLimiter::direct(Quota::per_second(limit_rate).allow_burst(NonZeroU32::MIN))
+            }),
+        })
+    }
+
+    /// Makes a GET request to `url`, retrying with exponential backoff while the API
+    /// answers 429 Too Many Requests or the connection fails on the way (i.e a reset
+    /// or a DNS hiccup). An answer of the API, even an error status, isn't retried.
+    ///
+    /// Every scheduled retry is reported on stderr, unless `quiet` is set, so a slow
+    /// command can be told apart from a hung one.
+    pub async fn fetch(&self, url: &str) -> Result<Response, reqwest::Error> {
+        let mut backoff = INITIAL_BACKOFF;
+
+        if self.show_url {
+            eprintln!("{} {url}", paint(Color::Purple, "GET"));
+        }
+
+        for attempt in 1..=MAX_RETRIES {
+            self.throttle().await;
+            let (delay, reason) = match self.client.get(url).send().await {
+                Ok(response) if response.status() != StatusCode::TOO_MANY_REQUESTS => {
+                    return Ok(response)
+                }
+                // The API may tell us how long to wait, otherwise we use our own backoff
+                Ok(response) => (
+                    retry_after(&response).unwrap_or(backoff),
+                    "Rate limited by the API (HTTP 429)".to_string(),
+                ),
+                Err(error) if is_transient(&error) => {
+                    (backoff, format!("Couldn't reach the API ({error})"))
+                }
+                Err(error) => return Err(error),
+            };
+
+            if !self.quiet {
+                eprintln!(
+                    "{}",
+                    paint(
+                        Color::Yellow,
+                        format!(
+                            "[!] {reason}, retry {attempt}/{MAX_RETRIES} in {}s",
+                            delay.as_secs()
+                        )
+                    )
+                );
+            }
+
+            tokio::time::sleep(delay).await;
+            backoff *= 2;
+        }
+
+        // Last attempt, whatever the API answers is handed back to the caller
+        self.throttle().await;
+        self.client.get(url).send().await
+    }
+
+    /// Waits until --limit-rate allows another request
+    async fn throttle(&self) {
+        if let Some(limiter) = &self.limiter {
+            limiter.until_ready().await;
+        }
+    }
+
+    /// Makes a GET request to `url` like `fetch` and returns the body, an error
+    /// status of the API is an error too and so is the page the API serves, even
+    /// with a 200, to an IP it has banned
+    pub async fn fetch_text(&self, url: &str) -> Result<String, FetchError> {
+        let response = self.fetch(url).await?;
+        let status = response.status();
+        let body = response.text().await?;
+
+        if is_ban_page(&body) {
+            return Err(FetchError::Banned);
+        }
+
+        if !status.is_success() {
+            return Err(FetchError::Status(status));
+        }
+
+        Ok(body)
+    }
+}
+
+/// The API answers JSON or a bare number, a body that is neither and talks about
+/// a block or a ban is the page shown to the IPs it turned away
+fn is_ban_page(body: &str) -> bool {
+    if serde_json::from_str::<serde::de::IgnoredAny>(body).is_ok() {
+        return false;
+    }
+
+    let body = body.to_lowercase();
+    body.contains("blocked") || body.contains("banned")
+}
+
+/// Whether a request failed on the way to the API, so trying again may work: the
+/// connection couldn't be made, it timed out or the API reset it. Other request
+/// errors (i.e an invalid URL or a bad proxy) fail the same way on every attempt
+fn is_transient(error: &reqwest::Error) -> bool {
+    error.is_connect() || error.is_timeout() || is_connection_reset(error)
+}
+
+/// Whether the connection was reset or aborted under the request, the cause is an
+/// I/O error somewhere down the sources of the reqwest error
+fn is_connection_reset(error: &reqwest::Error) -> bool {
+    let mut source = std::error::Error::source(error);
+    while let Some(cause) = source {
+        if let Some(io_error) = cause.downcast_ref::<std::io::Error>() {
+            return matches!(
+                io_error.kind(),
+                std::io::ErrorKind::ConnectionReset | std::io::ErrorKind::ConnectionAborted
+            );
+        }
+        source = cause.source();
+    }
+
+    false
+}
+
+/// Reads the `Retry-After` header when it is expressed in seconds
+fn retry_after(response: &Response) -> Option<Duration> {
+    response
+        .headers()
+        .get(RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .parse()
+        .ok()
+        .map(Duration::from_secs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ban_pages_are_told_apart_from_the_data() {
+        assert!(is_ban_page(
+            "<html><body>Your IP has been blocked for abuse</body></html>"
+        ));
+        assert!(is_ban_page("You have been BANNED, try again later"));
+        assert!(!is_ban_page(r#"{"note": "blocked outputs"}"#));
+        assert!(!is_ban_page("820000"));
+        assert!(!is_ban_page("Transaction not found"));
+    }
+}
@@ -0,0 +1,91 @@
+use std::fs;
+
+use clap::ValueEnum;
+
+/// How the hashes or addresses of an --input-file are laid out
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum InputFormat {
+    /// One item per line, blank lines and lines starting with `#` are skipped
+    Lines,
+    /// A JSON array of strings
+    Json,
+    /// The first column of a CSV file, with an optional header row
+    Csv,
+}
+
+/// Names of the header of a CSV column holding hashes or addresses
+const CSV_HEADERS: [&str; 5] = ["hash", "txid", "tx_hash", "address", "addr"];
+
+/// Reads the hashes or addresses of a batch from the file at `path`
+pub fn read_batch_input(
+    path: &str,
+    format: InputFormat,
+) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    parse_batch_input(&fs::read_to_string(path)?, format)
+}
+
+pub fn parse_batch_input(
+    contents: &str,
+    format: InputFormat,
+) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let items = match format {
+        InputFormat::Lines => contents
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(str::to_string)
+            .collect(),
+        InputFormat::Json => serde_json::from_str::<Vec<String>>(contents)?
+            .into_iter()
+            .map(|item| item.trim().to_string())
+            .filter(|item| !item.is_empty())
+            .collect(),
+        InputFormat::Csv => {
+            let mut reader = csv::ReaderBuilder::new()
+                .has_headers(false)
+                .flexible(true)
+                .trim(csv::Trim::All)
+                .from_reader(contents.as_bytes());
+
+            let mut items = Vec::new();
+            for (i, record) in reader.records().enumerate() {
+                let record = record?;
+                let Some(item) = record.get(0).filter(|item| !item.is_empty()) else {
+                    continue;
+                };
+
+                if i == 0 && CSV_HEADERS.contains(&item.to_lowercase().as_str()) {
+                    continue;
+                }
+
+                items.push(item.to_string());
+            }
+
+            items
+        }
+    };
+
+    Ok(items)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_format_gives_the_same_items() {
+        let expected = vec!["abc".to_string(), "def".to_string()];
+
+        let lines = "abc\n\n# comment\n def \n";
+        let json = r#"["abc", "def"]"#;
+        let csv = "hash,value\nabc,1\ndef,2\n";
+
+        for (contents, format) in [
+            (lines, InputFormat::Lines),
+            (json, InputFormat::Json),
+            (csv, InputFormat::Csv),
+        ] {
+            assert_eq!(parse_batch_input(contents, format).unwrap(), expected);
+        }
+    }
+}
@@ -0,0 +1,53 @@
+use chrono::DateTime;
+use serde::Serialize;
+
+use crate::format::btc_amount;
+use crate::BitcoinAddress;
+
+/// One line of the ledger of an address, the amounts are in BTC
+#[derive(Debug, Serialize)]
+struct LedgerRow {
+    hash: String,
+    time: String,
+    value_in: String,
+    value_out: String,
+    balance: String,
+}
+
+/// Writes the transactions of the address to a CSV file at `path`, oldest first,
+/// with what each one paid to and spent from the address and the balance after it.
+///
+/// Only the latest page of the history comes with the address, so the number of
+/// transactions written is returned.
+pub fn write_address_csv(
+    path: &str,
+    bitcoin_address: &BitcoinAddress,
+) -> Result<usize, Box<dyn std::error::Error>> {
+    // The API lists the newest transaction first, the balance is rebuilt backwards
+    // from the current one
+    let mut balance = bitcoin_address.final_balance as i128;
+    let mut rows = Vec::new();
+    for tx in &bitcoin_address.txs {
+        let (value_in, value_out) = tx.flow(&bitcoin_address.address);
+
+        rows.push(LedgerRow {
+            hash: tx.hash.clone(),
+            time: DateTime::from_timestamp(tx.time, 0)
+                .map(|time| time.to_rfc3339())
+                .unwrap_or_default(),
+            value_in: btc_amount(value_in).to_string(),
+            value_out: btc_amount(value_out).to_string(),
+            balance: btc_amount(balance).to_string(),
+        });
+
+        balance -= value_in as i128 - value_out as i128;
+    }
+
+    let mut writer = csv::Writer::from_path(path)?;
+    for row in rows.iter().rev() {
+        writer.serialize(row)?;
+    }
+    writer.flush()?;
+
+    Ok(rows.len())
+}
@@ -0,0 +1,64 @@
+use serde::de::{Error, Unexpected};
+use serde::{Deserialize, Deserializer};
+
+/// A number as some explorers send it, as is or as a string (`"value": "12345"`)
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum NumberOrString {
+    Number(u64),
+    String(String),
+}
+
+impl NumberOrString {
+    fn into_u64<E: Error>(self) -> Result<u64, E> {
+        match self {
+            NumberOrString::Number(number) => Ok(number),
+            NumberOrString::String(text) => text
+                .trim()
+                .parse()
+                .map_err(|_| E::invalid_value(Unexpected::Str(&text), &"a whole number")),
+        }
+    }
+}
+
+/// For `#[serde(deserialize_with)]` on the amounts and counts of the API responses,
+/// accepts a number or a string holding one
+pub fn u64_or_string<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+    NumberOrString::deserialize(deserializer)?.into_u64()
+}
+
+/// Same as `u64_or_string` for an optional field, to use along `#[serde(default)]`
+pub fn option_u64_or_string<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<u64>, D::Error> {
+    Option::<NumberOrString>::deserialize(deserializer)?
+        .map(NumberOrString::into_u64)
+        .transpose()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Deserialize)]
+    struct Amounts {
+        #[serde(deserialize_with = "u64_or_string")]
+        value: u64,
+        #[serde(default, deserialize_with = "option_u64_or_string")]
+        fee: Option<u64>,
+    }
+
+    #[test]
+    fn numbers_may_come_as_strings() {
+        let parse = |json: &str| serde_json::from_str::<Amounts>(json);
+
+        let amounts = parse(r#"{"value": 12345, "fee": "250"}"#).unwrap();
+        assert_eq!((amounts.value, amounts.fee), (12_345, Some(250)));
+
+        let amounts = parse(r#"{"value": " 12345 "}"#).unwrap();
+        assert_eq!((amounts.value, amounts.fee), (12_345, None));
+
+        assert!(parse(r#"{"value": "12.5"}"#).is_err());
+        assert!(parse(r#"{"value": -1}"#).is_err());
+    }
+}
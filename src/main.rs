@@ -1,10 +1,71 @@
+mod address;
+mod batch;
+mod blocks;
+mod decode;
+mod dot;
+mod fees;
+mod fields;
+mod flow;
+mod format;
+mod history;
+mod html;
+mod http;
+mod input;
+mod ledger;
+mod lenient;
+mod output;
+mod prices;
+mod progress;
+mod report;
+mod snapshot;
+mod store;
+mod summary;
+mod verify;
+mod watch;
+
+use address::{classify_address_type, validate_address, AddressType, ScriptTally};
 use ansi_term::Color::{self, Red};
-use chrono::NaiveTime;
-use clap::Parser;
-use cli_table::{print_stdout, Cell, Style, Table};
+use batch::TransactionBatch;
+use blocks::{block_urls, check_range, fetch_blocks, print_blocks};
+use chrono::format::{Item, StrftimeItems};
+use chrono::{DateTime, Local, Utc};
+use clap::{Parser, ValueEnum};
+use cli_table::{Cell, CellStruct, Style, Table, TableStruct};
+use decode::{decode_raw_transaction, DecodedTransaction};
+use dot::transactions_to_dot;
+use fees::{
+    confirmation_blocks, fee_estimates_url, fee_rate, fee_verdict, fetch_fee_estimates,
+    BLOCK_INTERVAL_MINUTES,
+};
+use fields::{include_raw_values, select_fields, FieldSelection};
+use flow::print_flow;
+use format::{abbreviate, btc_amount, compact_hash, format_btc, format_fiat, RoundingMode};
+use futures::StreamExt;
+use history::print_history;
+use html::to_html;
+use http::{FetchError, HttpClient};
+use indicatif::ProgressBar;
+use input::{read_batch_input, InputFormat};
+use ledger::write_address_csv;
 use num_format::{Locale, ToFormattedString};
-use serde::Deserialize;
+use output::{
+    amount_cell, link, number_format, paint, parse_locale, parse_separator, print_table,
+    summary_border, summary_style, Alignment, Linked, OutputOptions, SummaryColor,
+};
+use prices::compare_price_sources;
+use progress::batch_progress;
+use report::{explain_address, AddressReport, AddressStatus};
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use snapshot::{load_snapshot, save_snapshot, AddressDiff};
+use std::collections::{BTreeMap, HashMap};
+use std::io::{IsTerminal, Write};
+use std::num::{NonZeroU32, NonZeroUsize};
+use std::time::{Duration, Instant};
+use store::Store;
+use summary::BatchSummary;
+use verify::verify_round_trip;
+use watch::{watch_address, watch_unconfirmed};
 
 /// btcAnalyser - A CLI tool to analyze recent Bitcoin transactions.
 ///
@@ -21,350 +82,2049 @@ struct Cli {
     #[arg(short = 'n', long)]
     number_outputs: Option<usize>,
 
-    #[arg(short = 'i', long)]
-    inspect_transaction: Option<String>,
+    /// Group the unconfirmed transactions in intervals of <minutes> by arrival time
+    #[arg(long, value_name = "minutes", value_parser = clap::value_parser!(u64).range(1..))]
+    group_time: Option<u64>,
+
+    /// Show only the <n> unconfirmed transactions paying the highest fee rate
+    #[arg(long, value_name = "n", conflicts_with = "number_outputs")]
+    top_fee: Option<usize>,
+
+    #[arg(short = 'i', long, num_args = 1.., value_delimiter = ',')]
+    inspect_transaction: Vec<String>,
+
+    #[arg(short = 'a', long, num_args = 1.., value_delimiter = ',')]
+    inspect_address: Vec<String>,
+
+    /// First block height of the range of blocks mode
+    #[arg(long, value_name = "height", requires = "to")]
+    from: Option<u64>,
+
+    /// Last block height of the range of blocks mode, included
+    #[arg(long, value_name = "height", requires = "from")]
+    to: Option<u64>,
+
+    /// Transaction hash or address for auto mode, which picks the right mode for it
+    #[arg(long, value_name = "hash|address")]
+    input: Option<String>,
+
+    /// Raw transaction to decode offline in decode mode, read from stdin when missing
+    #[arg(long, value_name = "rawtx")]
+    hex: Option<String>,
+
+    /// Read the hashes or addresses of the batch from this file, along with -i/-a
+    #[arg(long, value_name = "path")]
+    input_file: Option<String>,
+
+    /// Layout of the --input-file
+    #[arg(long, value_enum, default_value = "lines", requires = "input_file")]
+    input_format: InputFormat,
+
+    /// Stop a batch of several hashes or addresses at the first one that fails
+    #[arg(long, conflicts_with = "keep_going")]
+    fail_fast: bool,
+
+    /// Skip the hashes or addresses that fail and report how many did at the end, the
+    /// exit code is still 1 if any did (default)
+    #[arg(long)]
+    keep_going: bool,
+
+    /// Sort the rows of the multi-address table or of the unconfirmed table by this field
+    #[arg(long, value_enum)]
+    sort: Option<SortKey>,
+
+    /// Sort in descending order
+    #[arg(long, requires = "sort")]
+    desc: bool,
+
+    /// Abbreviate the addresses of the tables to their first and last <n> characters, 0 shows
+    /// them whole. The JSON, YAML and CSV outputs always have the full addresses
+    #[arg(long, value_name = "n", default_value_t = 0)]
+    abbrev_addresses: usize,
+
+    /// Abbreviate the hashes in the unconfirmed table to their first and last 8 characters
+    #[arg(long)]
+    compact_hash: bool,
+
+    /// Add a column with the amount actually transferred by each unconfirmed transaction,
+    /// without the change going back to its own input addresses
+    #[arg(long)]
+    net_flow: bool,
+
+    /// Leave the likely change out of the amount of each unconfirmed transaction, the
+    /// outputs paying back to one of its input addresses. Change sent to a new address
+    /// can't be told apart, so it still counts
+    #[arg(long)]
+    exclude_change: bool,
+
+    /// Add a From column with the address sending each unconfirmed transaction, the
+    /// input spending the most. The transactions the feed gives without their inputs
+    /// are fetched, --concurrency at a time
+    #[arg(long)]
+    show_senders: bool,
+
+    /// Show the node that relayed each unconfirmed transaction and whether it's a double spend
+    #[arg(long)]
+    relay_info: bool,
+
+    /// Show only the first <n> rows of the table
+    #[arg(long, value_name = "n", conflicts_with = "tail")]
+    head: Option<usize>,
+
+    /// Show only the last <n> rows of the table
+    #[arg(long, value_name = "n")]
+    tail: Option<usize>,
+
+    /// Number of decimals shown in the BTC amounts
+    #[arg(long, value_name = "n", default_value_t = 8, value_parser = clap::value_parser!(u32).range(0..=8))]
+    btc_precision: u32,
+
+    /// How the BTC amounts are rounded to --btc-precision decimals
+    #[arg(long, value_enum, default_value = "half-up")]
+    round: RoundingMode,
+
+    /// Fiat currency the amounts are converted to (i.e USD, EUR, GBP)
+    #[arg(long, env = "BTC_ANALYSER_CURRENCY", default_value = "USD")]
+    currency: String,
+
+    /// Warn when the bitcoin price the API gives is older than <seconds>
+    #[arg(long, value_name = "seconds")]
+    max_price_age: Option<u64>,
+
+    /// Base URL of the blockchain.info compatible API [default: https://blockchain.info]
+    #[arg(long, env = "BTC_ANALYSER_API_BASE")]
+    api_base: Option<String>,
+
+    /// Use the testnet explorer (unless --api-base is given) and accept testnet addresses
+    #[arg(long)]
+    testnet: bool,
+
+    /// Maximum number of seconds a request can take
+    #[arg(long, value_name = "seconds", env = "BTC_ANALYSER_TIMEOUT")]
+    timeout: Option<u64>,
+
+    /// Make at most this many requests per second to the API, handy for long batches
+    #[arg(long, value_name = "requests-per-second")]
+    limit_rate: Option<NonZeroU32>,
+
+    /// Send the requests through this HTTP or HTTPS proxy, i.e "http://proxy:8080",
+    /// handy when the API has blocked this IP
+    #[arg(long, value_name = "url")]
+    proxy: Option<String>,
+
+    /// Requests made at the same time for a batch of hashes, addresses or blocks, the
+    /// results are still shown in the given order
+    #[arg(long, value_name = "n", default_value = "4")]
+    concurrency: NonZeroUsize,
+
+    /// Show only BTC amounts, without fetching the bitcoin price
+    #[arg(long, alias = "btc-only")]
+    no_usd: bool,
+
+    /// Most decimals shown in the fiat amounts, the zeros past the cents are trimmed,
+    /// 0 rounds them to whole units
+    #[arg(
+        long,
+        visible_alias = "fiat-decimals",
+        value_name = "n",
+        default_value_t = 2
+    )]
+    output_precision_usd: usize,
+
+    /// Save the stats of the address to this file, to compare them later with --diff
+    #[arg(long, value_name = "path")]
+    snapshot: Option<String>,
+
+    /// Write the transactions of the address to this CSV file, with the balance after each one
+    #[arg(long, value_name = "path")]
+    csv: Option<String>,
+
+    /// Keep every transaction and address fetched in this SQLite database, created
+    /// when missing, so the runs build up a dataset
+    #[arg(long, value_name = "path")]
+    db: Option<String>,
+
+    /// Show what changed in the address since the snapshot saved in this file
+    #[arg(long, value_name = "path")]
+    diff: Option<String>,
+
+    /// Show the whole transaction history of the address, fetched and printed page by page
+    #[arg(long, conflicts_with = "watch")]
+    history: bool,
+
+    /// Keep only the transactions of the history confirmed at or after this block
+    /// height, and the unconfirmed ones
+    #[arg(long, value_name = "height", requires = "history")]
+    since_block: Option<u64>,
+
+    /// Keep polling the address every <seconds> and report when its balance changes, or
+    /// list the new unconfirmed transactions every <seconds> in unconfirmed mode
+    #[arg(long, value_name = "seconds", value_parser = clap::value_parser!(u64).range(1..))]
+    watch: Option<u64>,
+
+    /// Show the totals of the unconfirmed watch session every <n> refreshes
+    #[arg(long, value_name = "n", requires = "watch", value_parser = clap::value_parser!(u64).range(1..))]
+    stats_interval: Option<u64>,
+
+    /// Ring the bell and show a desktop notification when the watched address receives a payment
+    #[arg(long, requires = "watch")]
+    notify: bool,
+
+    /// Show only the outputs worth at least this many BTC, the totals still count all of them
+    #[arg(long, value_name = "btc", value_parser = parse_btc_amount)]
+    min_output_btc: Option<f64>,
+
+    /// Highlight the outputs worth more than this many BTC, i.e a whale transfer or a
+    /// typo in the amount
+    #[arg(long, value_name = "btc", value_parser = parse_btc_amount)]
+    max_output_btc: Option<f64>,
+
+    /// Show only the outputs paying someone else, leaving out the change going back to
+    /// an input address. Without a clear change every output is shown
+    #[arg(long)]
+    recipients_only: bool,
+
+    /// Sum up the outputs per value range (under 0.001, up to 0.01, up to 0.1 and from
+    /// 0.1 BTC) instead of listing them, for the batch payouts
+    #[arg(long)]
+    group_outputs_by_value_bucket: bool,
+
+    /// Show only the inputs and outputs of this address in the inspect tables, with
+    /// whether it sends, receives or both. The totals still count every row
+    #[arg(long, value_name = "addr")]
+    only_address: Option<String>,
+
+    /// Maximum number of rows of the inspect input/output tables, 0 shows all of them
+    #[arg(long, value_name = "n", default_value_t = 50)]
+    max_rows_per_table: usize,
+
+    /// Preview only the first <n> inputs of inspect mode, in on-chain order, the totals
+    /// still count all of them
+    #[arg(long, value_name = "n")]
+    first_n_inputs: Option<usize>,
+
+    /// Preview only the first <n> outputs of inspect mode, in on-chain order, the totals
+    /// still count all of them
+    #[arg(long, value_name = "n")]
+    first_n_outputs: Option<usize>,
+
+    /// Warn before showing a transaction with more inputs than this, 0 never warns
+    #[arg(long, value_name = "n", default_value_t = 500)]
+    max_inputs: usize,
+
+    /// Warn before showing a transaction with more outputs than this, 0 never warns
+    #[arg(long, value_name = "n", default_value_t = 500)]
+    max_outputs: usize,
+
+    /// Show a simple payment (one sender, one recipient and maybe change) as a single
+    /// "From → To" line instead of the tables
+    #[arg(long)]
+    simple: bool,
+
+    /// Draw the flow of the transaction, from its inputs to its outputs, before the tables
+    #[arg(long)]
+    flow: bool,
+
+    /// strftime format of the times in the unconfirmed table, i.e "%H:%M:%S" [default: %H:%M]
+    #[arg(long, value_name = "strftime", value_parser = parse_time_format)]
+    time_format: Option<String>,
+
+    /// Show the times of the unconfirmed table in 12-hour format, like --time-format "%I:%M %p"
+    #[arg(long = "12h", conflicts_with = "time_format")]
+    twelve_hour: bool,
+
+    /// Add the seconds to the times of the unconfirmed table, to tell apart the
+    /// transactions arriving in the same minute
+    #[arg(long, visible_alias = "seconds", conflicts_with = "time_format")]
+    precise_time: bool,
+
+    /// Check that parsing the transaction didn't drop anything, comparing its sums with the raw API response
+    #[arg(long)]
+    round_trip_verify: bool,
+
+    /// Explain the fee rate of the transaction compared with the current mempool rates
+    #[arg(long)]
+    explain_fee: bool,
+
+    /// Estimate how many blocks and minutes an unconfirmed transaction waits before
+    /// confirming, from its fee rate and the current mempool rates
+    #[arg(long, visible_alias = "confirmations-target")]
+    confirmation_eta: bool,
+
+    /// Print only this value of each inspected transaction, one per line, for scripts
+    #[arg(long, value_enum, value_name = "field")]
+    show: Option<ShownValue>,
+
+    /// Print the amounts of --show in satoshis instead of BTC
+    #[arg(long, requires = "show")]
+    satoshis: bool,
+
+    /// Sum up the activity of the address in a plain sentence under its table
+    #[arg(long)]
+    explain_address: bool,
 
-    #[arg(short = 'a', long)]
-    inspect_address: Option<String>,
+    /// Output format, `json` and `yaml` print the parsed results instead of the tables,
+    /// `messagepack` writes them as binary MessagePack (redirect it, it isn't meant
+    /// for a terminal), `html` prints them as a styled HTML table to embed in a report
+    /// and `dot` draws the transactions of inspect mode as a Graphviz graph
+    #[arg(long, value_enum, default_value = "table")]
+    format: OutputFormat,
+
+    /// Print the --format json output on a single line instead of indented
+    #[arg(long)]
+    json_compact: bool,
+
+    /// Keep only these fields in the --format json objects, in this order, `field:name` renames one
+    #[arg(long, value_name = "field[:name]", value_delimiter = ',')]
+    fields: Vec<FieldSelection>,
+
+    /// Write every amount of the JSON and YAML output both as the exact integer of
+    /// satoshis and as BTC (`value_satoshis`/`value_btc`, `amount_btc`)
+    #[arg(long)]
+    include_raw_values: bool,
+
+    /// Show only the bottom line: the total in unconfirmed mode, the totals and fee in
+    /// inspect mode and the balance in address mode, where the JSON and YAML output
+    /// becomes a report of the address with its amounts converted and checked
+    #[arg(long, alias = "summary-only")]
+    summary: bool,
+
+    /// Check the inputs and print the requests that would be made, without making any
+    #[arg(long)]
+    dry_run: bool,
+
+    /// Print on stderr the URL of every request made to the API
+    #[arg(long)]
+    show_url: bool,
+
+    /// Don't color the output
+    #[arg(long)]
+    no_color: bool,
+
+    /// Make the hashes and addresses links to their explorer page, in the terminals
+    /// that support OSC 8 hyperlinks (ignored without colors or out of a terminal)
+    #[arg(long)]
+    links: bool,
+
+    /// Thousands separator of the amounts (i.e " " or "."), the BTC amounts are only
+    /// grouped when it's given [default: ,]
+    #[arg(long, value_name = "separator", value_parser = parse_separator)]
+    thousands_sep: Option<String>,
+
+    /// Decimal separator of the amounts (i.e ",") [default: .]
+    #[arg(long, value_name = "separator", value_parser = parse_separator)]
+    decimal_sep: Option<String>,
+
+    /// Locale of the amounts (i.e "de", "fr-CH" or "pt-PT"), it picks the separators
+    /// and the side of the fiat amounts the currency symbol goes [default: en]
+    #[arg(long, value_name = "name", value_parser = parse_locale)]
+    locale: Option<Locale>,
+
+    /// Side of their column the BTC and fiat amounts of the tables are aligned to
+    #[arg(long, value_enum, default_value = "right")]
+    amount_align: Alignment,
+
+    /// Pad the amounts of the tables to this many characters, so the columns keep
+    /// the same width from one run to the next
+    #[arg(long, value_name = "n")]
+    amount_width: Option<usize>,
+
+    /// Color of the total rows: `purple` sets them apart, `body` uses the color of the
+    /// table above and `none` leaves them monochrome
+    #[arg(long, value_enum, default_value = "purple")]
+    summary_color: SummaryColor,
+
+    /// Plain output for awk/grep: no colors, no table borders and no thousands separators
+    #[arg(long)]
+    plain: bool,
+
+    /// Fetch the bitcoin price from every source of the API, print them side by side
+    /// with their spread and exit
+    #[arg(long)]
+    compare_price_sources: bool,
+
+    /// Print the raw JSON fetched from the API and exit, without parsing it
+    #[arg(long)]
+    raw_json: bool,
+
+    /// Keep the output to the bare minimum: no notices about rate-limited
+    /// requests, and only the number in count mode
+    #[arg(short = 'q', long)]
+    quiet: bool,
 }
 
-#[derive(Debug)]
-struct UndefinedTransaction {
-    hash: String,
-    amount_bitcoin: f64,
-    amount_usd: f64,
-    time: NaiveTime,
+/// How the results are printed
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum OutputFormat {
+    Table,
+    Json,
+    Yaml,
+    #[value(alias = "msgpack")]
+    Messagepack,
+    Dot,
+    Html,
 }
 
-impl UndefinedTransaction {
-    fn new(
-        hash: String,
-        amount_bitcoin: f64,
-        amount_usd: f64,
-        time: NaiveTime,
-    ) -> UndefinedTransaction {
-        UndefinedTransaction {
-            hash,
-            amount_bitcoin,
-            amount_usd,
-            time,
-        }
+/// Fields the rows can be sorted by, balance, n-tx and received sort the
+/// multi-address table and amount and time the unconfirmed table
+#[derive(Clone, Copy, ValueEnum)]
+enum SortKey {
+    Balance,
+    NTx,
+    Received,
+    Amount,
+    Time,
+}
+
+impl SortKey {
+    /// Whether the key sorts the unconfirmed table, otherwise it sorts the addresses
+    fn sorts_unconfirmed(self) -> bool {
+        matches!(self, SortKey::Amount | SortKey::Time)
     }
 }
 
-#[derive(Debug, Deserialize)]
+/// Values of a transaction --show can print on their own
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum ShownValue {
+    Fee,
+    TotalInput,
+    TotalOutput,
+    Confirmations,
+}
+
+#[derive(Debug, Serialize)]
+struct UndefinedTransaction {
+    hash: String,
+    amount_satoshis: u64,
+    amount_fiat: Option<f64>, // None when the bitcoin price isn't available
+    time: DateTime<Local>,    // When the transaction reached the mempool
+    relayed_by: String,       // IP of the node that relayed the transaction
+    double_spend: bool,
+    fee_rate: Option<f64>, // sat/vByte, None when the feed lacks the fee or the size
+    net_flow_satoshis: u64, // Outputs minus the change going back to the input addresses
+    sender: Option<String>, // Input address spending the most, see `primary_sender`
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 struct Transaction {
     inputs: Vec<Input>,
     out: Vec<Output>,
     hash: String,
+    // Size in bytes and weight in weight units (4 WU = 1 vByte)
+    #[serde(default)]
+    size: u64,
+    #[serde(default)]
+    weight: u64,
+    // Height of the block that confirmed it, missing while it's in the mempool
+    #[serde(default)]
+    block_height: Option<u64>,
 }
 
-#[derive(Debug, Deserialize)]
+impl Transaction {
+    /// Sum of the values spent by the inputs, in satoshis
+    fn total_input(&self) -> u64 {
+        self.inputs
+            .iter()
+            .filter_map(|input| input.prev_out.as_ref())
+            .map(|prev_out| prev_out.value)
+            .sum()
+    }
+
+    /// Outputs paying someone else than the sender, taking as change the outputs that
+    /// go back to one of the input addresses. `None` when the change isn't clear: no
+    /// output goes back to the inputs, or all of them do
+    fn recipients(&self) -> Option<Vec<&Output>> {
+        let input_addresses: Vec<&str> = self
+            .inputs
+            .iter()
+            .filter_map(|input| input.prev_out.as_ref()?.addr.as_deref())
+            .collect();
+
+        let recipients: Vec<&Output> = self
+            .out
+            .iter()
+            .filter(|output| {
+                output
+                    .addr
+                    .as_deref()
+                    .is_none_or(|addr| !input_addresses.contains(&addr))
+            })
+            .collect();
+
+        if recipients.is_empty() || recipients.len() == self.out.len() {
+            return None;
+        }
+
+        Some(recipients)
+    }
+
+    /// Sum of the values of the outputs, in satoshis
+    fn total_output(&self) -> u64 {
+        self.out.iter().map(|output| output.value).sum()
+    }
+
+    /// Virtual size in vBytes, rounded up as the nodes do
+    fn vsize(&self) -> u64 {
+        if self.weight == 0 {
+            self.size
+        } else {
+            self.weight.div_ceil(4)
+        }
+    }
+
+    /// Weight in weight units, 4 per byte when the API only gives the size
+    fn weight_units(&self) -> u64 {
+        if self.weight == 0 {
+            self.size * 4
+        } else {
+            self.weight
+        }
+    }
+
+    /// A coinbase transaction mints new coins, so it doesn't spend any previous output
+    fn is_coinbase(&self) -> bool {
+        self.inputs.iter().all(|input| input.prev_out.is_none())
+    }
+
+    /// Fee paid to the miner in satoshis, `None` when the outputs are worth more
+    /// than the inputs, which can't happen in a valid transaction
+    fn fee(&self) -> Option<u64> {
+        self.total_input().checked_sub(self.total_output())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 struct Input {
-    prev_out: PrevOut,
+    // Coinbase inputs don't spend a previous output
+    #[serde(default)]
+    prev_out: Option<PrevOut>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 struct PrevOut {
-    addr: String,
+    #[serde(default)]
+    addr: Option<String>,
+    #[serde(deserialize_with = "lenient::u64_or_string")]
     value: u64,
+    // The output spent, by the transaction that created it and its index there. The
+    // explorer identifies that transaction by its tx_index, other APIs by its txid
+    #[serde(default, alias = "txid", skip_serializing_if = "Option::is_none")]
+    hash: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    tx_index: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    n: Option<u32>,
 }
 
-#[derive(Debug, Deserialize)]
+impl PrevOut {
+    /// The output spent, i.e `136937e5…48969450:1` or `tx_index 8182746:1`
+    fn spends_from(&self, compact: bool) -> Option<String> {
+        let funding = match (&self.hash, self.tx_index) {
+            (Some(hash), _) if compact => compact_hash(hash),
+            (Some(hash), _) => hash.clone(),
+            (None, Some(tx_index)) => format!("tx_index {tx_index}"),
+            (None, None) => return None,
+        };
+
+        Some(match self.n {
+            Some(n) => format!("{funding}:{n}"),
+            None => funding,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 struct Output {
+    #[serde(deserialize_with = "lenient::u64_or_string")]
     value: u64,
-    addr: String,
+    // Non-standard outputs (i.e OP_RETURN) don't pay to an address
+    #[serde(default)]
+    addr: Option<String>,
+}
+
+/// Response of the unconfirmed transactions endpoint
+#[derive(Debug, Deserialize)]
+struct UnconfirmedFeed {
+    #[serde(default)]
+    txs: Vec<FeedTx>,
 }
 
+/// Transaction of the unconfirmed feed, the fields the feed may leave out get
+/// their default
 #[derive(Debug, Deserialize)]
+struct FeedTx {
+    #[serde(default)]
+    hash: String,
+    #[serde(default)]
+    inputs: Vec<Input>,
+    #[serde(default)]
+    out: Vec<Output>,
+    // Arrival time in the mempool, as a UNIX timestamp
+    #[serde(default)]
+    time: Option<i64>,
+    #[serde(default, deserialize_with = "lenient::option_u64_or_string")]
+    fee: Option<u64>,
+    #[serde(default)]
+    size: u64,
+    #[serde(default)]
+    weight: u64,
+    #[serde(default)]
+    relayed_by: Option<String>,
+    #[serde(default)]
+    double_spend: bool,
+}
+
+impl FeedTx {
+    /// Same virtual size as Transaction::vsize, from the weight when there is one
+    fn vsize(&self) -> u64 {
+        if self.weight == 0 {
+            self.size
+        } else {
+            self.weight.div_ceil(4)
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 struct BitcoinAddress {
+    address: String,
+    #[serde(deserialize_with = "lenient::u64_or_string")]
     n_tx: u64, // Number of transaction that this address made
+    #[serde(deserialize_with = "lenient::u64_or_string")]
     total_received: u64,
+    #[serde(deserialize_with = "lenient::u64_or_string")]
     total_sent: u64,
+    #[serde(deserialize_with = "lenient::u64_or_string")]
     final_balance: u64,
+    // Latest page of the address history, not kept in the snapshots
+    #[serde(default, skip_serializing)]
+    txs: Vec<AddressTransaction>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct AddressTransaction {
+    #[serde(default)]
+    hash: String,
+    time: i64, // Unix timestamp
+    #[serde(default)]
+    inputs: Vec<Input>,
+    #[serde(default)]
+    out: Vec<Output>,
+    // Missing while the transaction is unconfirmed
+    #[serde(default)]
+    block_height: Option<u64>,
+}
+
+impl AddressTransaction {
+    /// What the transaction paid to `address` and what it spent from it, in satoshis
+    fn flow(&self, address: &str) -> (u64, u64) {
+        let received = self
+            .out
+            .iter()
+            .filter(|output| output.addr.as_deref() == Some(address))
+            .map(|output| output.value)
+            .sum();
+        let sent = self
+            .inputs
+            .iter()
+            .filter_map(|input| input.prev_out.as_ref())
+            .filter(|prev_out| prev_out.addr.as_deref() == Some(address))
+            .map(|prev_out| prev_out.value)
+            .sum();
+
+        (received, sent)
+    }
+}
+
+impl BitcoinAddress {
+    /// Whether what the address received minus what it sent gives its balance
+    fn is_reconciled(&self) -> bool {
+        self.total_received.checked_sub(self.total_sent) == Some(self.final_balance)
+    }
+
+    /// Time of the oldest transaction fetched, and whether it's the first one the
+    /// address ever made (the API only returns the latest page of the history)
+    fn first_seen(&self) -> Option<(DateTime<Utc>, bool)> {
+        let oldest = self.txs.iter().map(|tx| tx.time).min()?;
+        let is_complete = self.txs.len() as u64 >= self.n_tx;
+
+        DateTime::from_timestamp(oldest, 0).map(|first_seen| (first_seen, is_complete))
+    }
+
+    /// Time of the most recent transaction of the address
+    fn last_seen(&self) -> Option<DateTime<Utc>> {
+        DateTime::from_timestamp(self.txs.iter().map(|tx| tx.time).max()?, 0)
+    }
 }
 
 const SATOSHIS_PER_BTC: u32 = 100_000_000;
 
+const MAINNET_API_BASE: &str = "https://blockchain.info";
+const TESTNET_API_BASE: &str = "https://testnet.blockchain.info";
+
 #[tokio::main]
-async fn main() -> Result<(), Box<dyn std::error::Error>> {
+async fn main() {
+    // Printed with Display, the errors read as the advice they give
+    if let Err(error) = run().await {
+        eprintln!("Error: {error}");
+        std::process::exit(1);
+    }
+}
+
+async fn run() -> Result<(), Box<dyn std::error::Error>> {
     // CTRL-C Handlig
     ctrlc::set_handler(|| {
-        println!("{}", Red.paint("\n[!] Exiting...\n"));
+        println!("{}", paint(Red, "\n[!] Exiting...\n"));
         std::process::exit(1);
     })
     .expect("Error setting Ctrl-C handler");
 
     // Handling Command line arguments
-    let cli = Cli::parse();
+    let mut cli = Cli::parse();
+    output::init(OutputOptions::from_cli(&cli));
 
-    // Global variables, that contain the URL to makes request to the API
+    // The items of the input file are added to the ones given on the command line
+    if let Some(path) = &cli.input_file {
+        let items = read_batch_input(path, cli.input_format)?;
+        match cli.exploration_mode.as_deref() {
+            Some("inspect") => cli.inspect_transaction.extend(items),
+            Some("address") => cli.inspect_address.extend(items),
+            _ => {}
+        }
+    }
 
-    let unconfirmed_transactions_url =
-        "https://blockchain.info/unconfirmed-transactions?format=json";
-    let inspect_transaction_url = "https://blockchain.info/rawtx/";
-    let inspect_address_url = "https://blockchain.info/rawaddr/";
-    let bitcoin_price_url = "https://api.blockchain.info/stats";
+    // Auto mode hands the input over to the mode that fits it
+    if cli.exploration_mode.as_deref() == Some("auto") {
+        let Some(input) = cli.input.clone() else {
+            println!("{}", paint(Color::Cyan, "Provide a transaction hash or an address (i.e -e auto --input bc1q9jk7j04lcjzdns6wjegeju78mxq27reg5e4wkycrs407yd0r07psvs8x7u)\n"));
+            help_panel();
+            std::process::exit(1);
+        };
 
-    match cli.exploration_mode.as_deref() {
-        // We check if the user specified a number of outputs, by default is 100
-        Some("unconfirmed_transactions") => {
-            let number_outputs = match cli.number_outputs {
-                Some(number_outputs) => number_outputs,
-                None => 100,
-            };
+        if is_transaction_hash(&input) {
+            cli.exploration_mode = Some("inspect".to_string());
+            cli.inspect_transaction.push(input);
+        } else if validate_address(&input, cli.testnet).is_ok() {
+            cli.exploration_mode = Some("address".to_string());
+            cli.inspect_address.push(input);
+        } else {
+            report_error(
+                cli.format,
+                format!("[!] {input} is neither a transaction hash nor a Bitcoin address"),
+            );
+            std::process::exit(1);
+        }
+    }
 
-            // Call the function unconfirmed_transactions to get the number_output od unconfirmed transaction in a vector
-            let undefined_transaction_vec = unconfirmed_transactions(
-                unconfirmed_transactions_url,
-                bitcoin_price_url,
-                number_outputs,
-            )
-            .await?;
+    // The binary output would garble the terminal
+    if cli.format == OutputFormat::Messagepack && std::io::stdout().is_terminal() {
+        report_error(
+            cli.format,
+            "[!] --format messagepack writes binary data, redirect it to a file or a pipe"
+                .to_string(),
+        );
+        std::process::exit(1);
+    }
 
-            // Print the table
-            let table: Vec<_> = undefined_transaction_vec
-                .iter()
-                .map(|undefined_transaction| {
-                    vec![
-                        Color::Yellow
-                            .paint(undefined_transaction.hash.clone())
-                            .cell(),
-                        Color::Yellow
-                            .paint(format!("{} BTC", undefined_transaction.amount_bitcoin))
-                            .cell(),
-                        Color::Yellow
-                            .paint(format!(
-                                "${}{:.5}",
-                                (undefined_transaction.amount_usd.trunc() as u64)
-                                    .to_formatted_string(&Locale::en),
-                                { undefined_transaction.amount_usd.fract() }
-                            ))
-                            .cell(),
-                        Color::Yellow
-                            .paint(format!("{}", undefined_transaction.time.format("%H:%M")))
-                            .cell(),
-                    ]
-                })
-                .collect();
+    // Only the transactions make a graph
+    if cli.format == OutputFormat::Dot && cli.exploration_mode.as_deref() != Some("inspect") {
+        report_error(
+            cli.format,
+            "[!] --format dot is only available in inspect mode".to_string(),
+        );
+        std::process::exit(1);
+    }
 
-            let undefined_transaction_table = cli_table::Table::table(table)
-                .title(vec![
-                    "Hash".cell().bold(true),
-                    "Bitcoin".cell().bold(true),
-                    "Amount(USD)".cell().bold(true),
-                    "Time".cell().bold(true),
-                ])
-                .foreground_color(Some(cli_table::Color::Yellow));
+    let http = HttpClient::new(
+        cli.timeout.map(Duration::from_secs),
+        cli.quiet,
+        cli.show_url,
+        cli.limit_rate,
+        cli.proxy.as_deref(),
+    )?;
+    let currency = cli.currency.to_uppercase();
+
+    // Global variables, that contain the URL to makes request to the API
+    let api_base = match &cli.api_base {
+        Some(api_base) => api_base.trim_end_matches('/'),
+        None if cli.testnet => TESTNET_API_BASE,
+        None => MAINNET_API_BASE,
+    };
+    let unconfirmed_transactions_url = format!("{api_base}/unconfirmed-transactions?format=json");
+    let inspect_transaction_url = format!("{api_base}/rawtx/");
+    let inspect_address_url = format!("{api_base}/rawaddr/");
+    let unconfirmed_count_url = format!("{api_base}/q/unconfirmedcount");
+    let block_count_url = format!("{api_base}/q/getblockcount");
+    let fee_estimates_url = fee_estimates_url(api_base);
+    if fee_estimates_url.is_none() && (cli.explain_fee || cli.confirmation_eta) {
+        report_error(
+            cli.format,
+            "[!] The fee estimates are only served for mainnet, --explain-fee and --confirmation-eta need an explorer that serves them with --testnet (see --api-base)".to_string(),
+        );
+        std::process::exit(1);
+    }
+    // Testnet explorers don't serve a price, it always comes from mainnet
+    // No price is fetched at all with --no-usd
+    let bitcoin_price_url = if cli.no_usd {
+        None
+    } else if cli.testnet && cli.api_base.is_none() {
+        Some(bitcoin_price_url(MAINNET_API_BASE, &currency))
+    } else {
+        Some(bitcoin_price_url(api_base, &currency))
+    };
 
-            print_stdout(undefined_transaction_table)?;
-            println!();
+    // The requests for the data of the mode, the price and fee estimates aside
+    let data_urls: Vec<String> = match cli.exploration_mode.as_deref() {
+        Some("unconfirmed_transactions") => vec![unconfirmed_transactions_url.clone()],
+        Some("inspect") => cli
+            .inspect_transaction
+            .iter()
+            .map(|hash| format!("{inspect_transaction_url}{hash}"))
+            .collect(),
+        Some("address") => cli
+            .inspect_address
+            .iter()
+            .map(|address| format!("{inspect_address_url}{address}"))
+            .collect(),
+        Some("count") => vec![unconfirmed_count_url.clone()],
+        Some("ping") => vec![block_count_url.clone()],
+        Some("blocks") => match (cli.from, cli.to) {
+            (Some(from), Some(to)) => {
+                if let Err(message) = check_range(from, to) {
+                    report_error(cli.format, message);
+                    std::process::exit(1);
+                }
+                block_urls(api_base, from, to)
+            }
+            _ => Vec::new(),
+        },
+        _ => Vec::new(),
+    };
 
-            // Show the total amount of money that was transfer
-            let total: f64 = undefined_transaction_vec
-                .iter()
-                .map(|ut| ut.amount_usd)
-                .sum();
-            let table = vec![vec![
-                Color::Purple.paint("Total Amount").cell(),
-                Color::Purple
-                    .paint(format!(
-                        "${}{:.5}",
-                        (total.trunc() as u64).to_formatted_string(&Locale::en),
-                        { total.fract() }
-                    ))
-                    .cell(),
-            ]]
-            .table();
+    // Check the inputs and show the requests that would be made, without making them
+    if cli.dry_run {
+        let mut invalid = Vec::new();
+        if cli.exploration_mode.as_deref() == Some("inspect") {
+            for hash in &cli.inspect_transaction {
+                if !is_transaction_hash(hash) {
+                    invalid.push(format!("[!] {hash}: not a transaction hash"));
+                }
+            }
+        }
+        if cli.exploration_mode.as_deref() == Some("address") {
+            for address in &cli.inspect_address {
+                if let Err(error) = validate_address(address, cli.testnet) {
+                    invalid.push(format!("[!] {address}: {error}"));
+                }
+            }
+        }
 
-            print_stdout(table.foreground_color(Some(cli_table::Color::Magenta)))?;
+        if !invalid.is_empty() {
+            for message in invalid {
+                report_error(cli.format, message);
+            }
+            std::process::exit(1);
+        }
 
-            // exit the program
+        if cli.exploration_mode.as_deref() == Some("decode") {
+            println!(
+                "{}",
+                paint(Color::Cyan, "decode works offline, it makes no request")
+            );
             std::process::exit(0);
         }
-        Some("inspect") => {
-            if cli.inspect_transaction.is_none() {
-                println!("{}", Color::Cyan.paint("Provide a transaction hash (i.e -i 136937e5a742645ce873f079f8668aefdc2d06b8172e903d031a8bfb48969450)\n"));
-                help_panel();
-                std::process::exit(1);
+
+        if data_urls.is_empty() {
+            help_panel();
+            std::process::exit(1);
+        }
+
+        let mut urls = data_urls;
+        match cli.exploration_mode.as_deref() {
+            Some("unconfirmed_transactions") | Some("address") => {
+                urls.extend(bitcoin_price_url.clone())
+            }
+            Some("inspect") if cli.explain_fee || cli.confirmation_eta => {
+                urls.extend(fee_estimates_url.clone())
+            }
+            Some("inspect") if cli.show == Some(ShownValue::Confirmations) => {
+                urls.push(block_count_url.clone())
             }
+            _ => {}
+        }
 
-            match inspect_transaction(&cli.inspect_transaction.unwrap(), inspect_transaction_url)
-                .await
-            {
-                Ok(transaction) => {
-                    // Extract values from inputs[].prev_out.value
-                    let total_inputs: Vec<&PrevOut> = transaction
-                        .inputs
-                        .iter()
-                        .map(|input| &input.prev_out)
-                        .collect();
-                    let total_input: u64 = total_inputs
-                        .iter()
-                        .map(|prev_output| prev_output.value)
-                        .sum();
-                    let total_input: f64 = total_input as f64 / SATOSHIS_PER_BTC as f64;
-
-                    // Extract values from out[].value
-                    let total_outputs: Vec<&Output> = transaction.out.iter().collect();
-                    let total_output: u64 = total_outputs.iter().map(|output| output.value).sum();
-                    let total_output: f64 = total_output as f64 / SATOSHIS_PER_BTC as f64;
-
-                    // Show Total inputs table
-                    let totals_table = vec![vec![
-                        Color::Yellow
-                            .paint(format!("{} BTC", total_input.to_string()))
-                            .cell(),
-                        Color::Yellow
-                            .paint(format!("{} BTC", total_output.to_string()))
-                            .cell(),
-                    ]]
-                    .table()
-                    .title(vec![
-                        "Total Input".cell().bold(true),
-                        "Total Output".cell().bold(true),
-                    ]);
+        match cli.format {
+            OutputFormat::Table | OutputFormat::Dot => {
+                for url in &urls {
+                    println!("{} {url}", paint(Color::Purple, "GET"));
+                }
+            }
+            _ => print_result(&cli, &serde_json::json!({ "requests": urls }))?,
+        }
 
-                    print_stdout(totals_table.foreground_color(Some(cli_table::Color::Yellow)))?;
+        std::process::exit(0);
+    }
 
-                    // Show Address inputs and its Value table
-                    let table: Vec<_> = total_inputs
-                        .iter()
-                        .map(|prev_out| {
-                            let btc = prev_out.value as f64 / SATOSHIS_PER_BTC as f64;
-                            vec![
-                                Color::Green.paint(prev_out.addr.clone()).cell(),
-                                Color::Green
-                                    .paint(format!("{} BTC", btc.to_string()))
-                                    .cell(),
-                            ]
-                        })
-                        .collect();
+    // Bypass the parsing and tables and just hand over what the API answered
+    if cli.raw_json {
+        if data_urls.is_empty() {
+            help_panel();
+            std::process::exit(1);
+        }
 
-                    let table_inputs = cli_table::Table::table(table)
-                        .title(vec![
-                            "Address (input)".cell().bold(true),
-                            "Value".cell().bold(true),
-                        ])
-                        .foreground_color(Some(cli_table::Color::Green));
+        for url in data_urls {
+            println!("{}", http.fetch_text(&url).await?);
+        }
 
-                    print_stdout(table_inputs)?;
-                    println!();
+        std::process::exit(0);
+    }
 
-                    // Show Address Outputs and its Value table
-                    let table: Vec<_> = total_outputs
-                        .iter()
-                        .map(|output| {
-                            let btc = output.value as f64 / SATOSHIS_PER_BTC as f64;
-                            vec![
-                                Color::Green.paint(output.addr.clone()).cell(),
-                                Color::Green
-                                    .paint(format!("{} BTC", btc.to_string()))
-                                    .cell(),
-                            ]
-                        })
-                        .collect();
+    if cli.compare_price_sources {
+        // Like the price of the modes, testnet explorers don't serve one
+        let price_api_base = if cli.testnet && cli.api_base.is_none() {
+            MAINNET_API_BASE
+        } else {
+            api_base
+        };
+        compare_price_sources(&cli, &http, price_api_base, &currency).await?;
+        std::process::exit(0);
+    }
 
-                    let table_outputs = cli_table::Table::table(table)
-                        .title(vec![
-                            "Address (output)".cell().bold(true),
-                            "Value".cell().bold(true),
-                        ])
-                        .foreground_color(Some(cli_table::Color::Green));
+    let store = cli.db.as_deref().map(Store::open).transpose()?;
 
-                    print_stdout(table_outputs)?;
-                }
-                Err(_) => println!(
-                    "{}",
-                    Color::Red.paint("[!] There is not transaction with the hash received")
-                ),
-            };
-        }
-        Some("address") => {
-            if cli.inspect_address.is_none() {
-                println!("{}", Color::Cyan.paint("Provide a Bitcoin Addres (i.e -a bc1q9jk7j04lcjzdns6wjegeju78mxq27reg5e4wkycrs407yd0r07psvs8x7u)\n"));
-                help_panel();
-                std::process::exit(1);
+    match cli.exploration_mode.as_deref() {
+        // We check if the user specified a number of outputs, by default is 100
+        Some("unconfirmed_transactions") => {
+            if let Some(seconds) = cli.watch {
+                watch_unconfirmed(
+                    &cli,
+                    &http,
+                    &unconfirmed_transactions_url,
+                    &unconfirmed_count_url,
+                    bitcoin_price_url.as_deref(),
+                    Duration::from_secs(seconds),
+                )
+                .await?;
             }
 
-            match inspect_address(&cli.inspect_address.unwrap(), inspect_address_url).await {
-                Ok(bitcoin_address) => {
-                    let bitcoin_price_query = reqwest::get(bitcoin_price_url)
-                        .await
-                        .unwrap()
-                        .text()
-                        .await
-                        .unwrap();
-
-                    let parsed: Value = serde_json::from_str(&bitcoin_price_query)?;
-                    let bitcoin_price = parsed["market_price_usd"].as_f64().unwrap();
-
-                    let bitcoin_address_table = {
-                        let total_received_btc =
-                            bitcoin_address.total_received as f64 / SATOSHIS_PER_BTC as f64;
-                        let total_received_usd = total_received_btc * bitcoin_price;
-                        let total_sent_btc =
-                            bitcoin_address.total_sent as f64 / SATOSHIS_PER_BTC as f64;
-                        let total_sent_usd = total_sent_btc * bitcoin_price;
+            let time_format = time_format(&cli);
+
+            // The top fee view ranks the whole feed
+            let number_outputs = match cli.top_fee {
+                Some(_) => usize::MAX,
+                None => cli.number_outputs.unwrap_or(100),
+            };
+
+            // Call the function unconfirmed_transactions to get the number_output od unconfirmed transaction in a vector
+            let (mut undefined_transaction_vec, quote) = unconfirmed_transactions(
+                &http,
+                &unconfirmed_transactions_url,
+                bitcoin_price_url.as_deref(),
+                &currency,
+                number_outputs,
+                cli.exclude_change,
+                cli.format,
+            )
+            .await?;
+
+            if let Some(quote) = &quote {
+                check_price_age(&cli, quote, &currency);
+            }
+
+            if let Some(top_fee) = cli.top_fee {
+                let mut top_transactions = top_by_fee_rate(undefined_transaction_vec, top_fee);
+
+                // Only the senders of the rows shown are looked up
+                if cli.show_senders {
+                    resolve_senders(&cli, &mut top_transactions, &inspect_transaction_url, &http)
+                        .await;
+                }
+
+                if cli.format != OutputFormat::Table {
+                    print_result(&cli, &top_transactions)?;
+                    std::process::exit(0);
+                }
+
+                print_table(top_fee_table(&cli, &top_transactions))?;
+                std::process::exit(0);
+            }
+
+            if let Some(sort) = cli.sort {
+                if !sort.sorts_unconfirmed() {
+                    report_error(
+                        cli.format,
+                        "[!] The unconfirmed transactions can only be sorted by amount or time"
+                            .to_string(),
+                    );
+                    std::process::exit(1);
+                }
+
+                sort_unconfirmed(&mut undefined_transaction_vec, sort, cli.desc);
+            }
+
+            // Keep only the rows selected with --head/--tail
+            let mut undefined_transaction_vec =
+                select_rows(undefined_transaction_vec, cli.head, cli.tail);
+
+            if let Some(minutes) = cli.group_time {
+                let time_buckets = group_by_time(&undefined_transaction_vec, minutes);
+
+                if cli.format != OutputFormat::Table {
+                    print_result(&cli, &time_buckets)?;
+                    std::process::exit(0);
+                }
+
+                let table: Vec<_> = time_buckets
+                    .iter()
+                    .map(|time_bucket| {
+                        vec![
+                            paint(
+                                Color::Yellow,
+                                format!(
+                                    "{} - {}",
+                                    time_bucket.start.format(time_format),
+                                    time_bucket.end.format(time_format)
+                                ),
+                            )
+                            .cell(),
+                            paint(Color::Yellow, time_bucket.transactions).cell(),
+                            amount_cell(
+                                Color::Yellow,
+                                format_btc(
+                                    time_bucket.amount_satoshis,
+                                    cli.btc_precision,
+                                    cli.round,
+                                ),
+                            ),
+                        ]
+                    })
+                    .collect();
+
+                let time_buckets_table = cli_table::Table::table(table)
+                    .title(vec![
+                        "Arrival Time".cell().bold(true),
+                        "Transactions".cell().bold(true),
+                        "Bitcoin".cell().bold(true),
+                    ])
+                    .foreground_color(Some(cli_table::Color::Yellow));
+
+                print_table(time_buckets_table)?;
+                std::process::exit(0);
+            }
+
+            if cli.show_senders {
+                resolve_senders(
+                    &cli,
+                    &mut undefined_transaction_vec,
+                    &inspect_transaction_url,
+                    &http,
+                )
+                .await;
+            }
+
+            if cli.format != OutputFormat::Table {
+                print_result(&cli, &undefined_transaction_vec)?;
+                std::process::exit(0);
+            }
+
+            // Without a usable price only the BTC amounts are shown
+            let show_fiat = undefined_transaction_vec
+                .iter()
+                .all(|ut| ut.amount_fiat.is_some());
+
+            // Print the table
+            let table: Vec<_> = undefined_transaction_vec
+                .iter()
+                .map(|undefined_transaction| {
+                    let hash = unconfirmed_hash_label(&cli, undefined_transaction);
+                    let color = unconfirmed_row_color(undefined_transaction);
+
+                    let mut row = vec![
+                        paint(color, hash).cell(),
+                        amount_cell(
+                            color,
+                            format_btc(
+                                undefined_transaction.amount_satoshis,
+                                cli.btc_precision,
+                                cli.round,
+                            ),
+                        ),
+                        paint(
+                            color,
+                            format!("{}", undefined_transaction.time.format(time_format)),
+                        )
+                        .cell(),
+                    ];
+
+                    if cli.net_flow {
+                        row.insert(
+                            2,
+                            amount_cell(
+                                color,
+                                format_btc(
+                                    undefined_transaction.net_flow_satoshis,
+                                    cli.btc_precision,
+                                    cli.round,
+                                ),
+                            ),
+                        );
+                    }
+
+                    if let Some(amount_fiat) = undefined_transaction.amount_fiat {
+                        row.insert(
+                            2,
+                            amount_cell(
+                                color,
+                                format_fiat(
+                                    amount_fiat,
+                                    &currency,
+                                    &number_format(),
+                                    cli.output_precision_usd,
+                                ),
+                            ),
+                        );
+                    }
+
+                    if cli.show_senders {
+                        row.insert(
+                            1,
+                            paint(color, sender_label(&cli, undefined_transaction)).cell(),
+                        );
+                    }
+
+                    if cli.relay_info {
+                        row.push(paint(color, undefined_transaction.relayed_by.clone()).cell());
+                        row.push(if undefined_transaction.double_spend {
+                            paint(Color::Red.bold(), "⚠ YES").cell()
+                        } else {
+                            paint(color, "no").cell()
+                        });
+                    }
+
+                    row
+                })
+                .collect();
+
+            let mut title = vec![
+                "Hash".cell().bold(true),
+                "Bitcoin".cell().bold(true),
+                "Time".cell().bold(true),
+            ];
+            if cli.net_flow {
+                title.insert(2, "Net Flow".cell().bold(true));
+            }
+            if show_fiat {
+                title.insert(2, format!("Amount({currency})").cell().bold(true));
+            }
+            if cli.show_senders {
+                title.insert(1, "From".cell().bold(true));
+            }
+            if cli.relay_info {
+                title.push("Relayed By".cell().bold(true));
+                title.push("Double Spend".cell().bold(true));
+            }
+
+            let undefined_transaction_table = cli_table::Table::table(table)
+                .title(title)
+                .foreground_color(Some(cli_table::Color::Yellow));
+
+            if !cli.summary {
+                print_table(undefined_transaction_table)?;
+                println!();
+            }
+
+            // Show the total amount of money that was transfer
+            let total = if show_fiat {
+                let total: f64 = undefined_transaction_vec
+                    .iter()
+                    .filter_map(|ut| ut.amount_fiat)
+                    .sum();
+                format_fiat(total, &currency, &number_format(), cli.output_precision_usd)
+            } else {
+                let total: u64 = undefined_transaction_vec
+                    .iter()
+                    .map(|ut| ut.amount_satoshis)
+                    .sum();
+                format_btc(total, cli.btc_precision, cli.round)
+            };
+            let total_style = summary_style(Color::Yellow);
+            let mut footer = vec![vec![
+                paint(total_style, "Total Amount").cell(),
+                paint(total_style, total).cell(),
+            ]];
+            if let Some(quote) = quote.filter(|_| show_fiat) {
+                footer.push(vec![
+                    paint(total_style, "Bitcoin Price").cell(),
+                    paint(total_style, price_label(&cli, &quote, &currency)).cell(),
+                ]);
+            }
+            let table = footer.table();
+
+            print_table(table.foreground_color(summary_border(Color::Yellow)))?;
+
+            // exit the program
+            std::process::exit(0);
+        }
+        Some("inspect") => {
+            if cli.inspect_transaction.is_empty() {
+                println!("{}", paint(Color::Cyan, "Provide a transaction hash (i.e -i 136937e5a742645ce873f079f8668aefdc2d06b8172e903d031a8bfb48969450)\n"));
+                help_panel();
+                std::process::exit(1);
+            }
+
+            // Just the value asked for, a line per hash and the errors on stderr
+            if let Some(shown) = cli.show {
+                let chain_tip = match shown {
+                    ShownValue::Confirmations => Some(block_count(&http, &block_count_url).await?),
+                    _ => None,
+                };
+
+                // The values are printed as they come, that shows the progress
+                let mut batch = TransactionBatch::new(
+                    &cli.inspect_transaction,
+                    &inspect_transaction_url,
+                    &http,
+                    cli.concurrency,
+                    ProgressBar::hidden(),
+                );
+                let mut failed = false;
+                for hash in &cli.inspect_transaction {
+                    match batch.next(hash).await {
+                        Ok((transaction, _)) => {
+                            if let Some(store) = &store {
+                                store.record_transaction(&transaction)?;
+                            }
+                            println!("{}", shown_value(&cli, &transaction, shown, chain_tip))
+                        }
+                        Err(error) => {
+                            eprintln!("[!] Couldn't fetch {hash}: {error}");
+                            failed = true;
+                        }
+                    }
+                }
+
+                std::process::exit(if failed { 1 } else { 0 });
+            }
+
+            let is_batch = cli.inspect_transaction.len() > 1;
+            let mut summary = BatchSummary::new(cli.inspect_transaction.len());
+            let mut transactions = Vec::new();
+            let mut status_rows = Vec::new();
+            // The tables are printed as the transactions come, that shows the
+            // progress, the other formats are printed once the batch is done
+            let mut batch = TransactionBatch::new(
+                &cli.inspect_transaction,
+                &inspect_transaction_url,
+                &http,
+                cli.concurrency,
+                batch_progress(
+                    cli.inspect_transaction.len(),
+                    cli.quiet || cli.format == OutputFormat::Table,
+                ),
+            );
+
+            for hash in &cli.inspect_transaction {
+                if is_batch && cli.format == OutputFormat::Table {
+                    println!(
+                        "{}",
+                        paint(
+                            Color::Purple.bold(),
+                            format!(
+                                "Transaction {}",
+                                link(Linked::Transaction, hash, hash.clone())
+                            )
+                        )
+                    );
+                }
+
+                match batch.next(hash).await {
+                    Ok((transaction, raw_json)) => {
+                        if cli.round_trip_verify {
+                            for mismatch in verify_round_trip(&raw_json, &transaction)? {
+                                report_warning(cli.format, format!(
+                                    "[!] Round-trip check of {hash}: {mismatch}, the parsing may have dropped data"
+                                ));
+                            }
+                        }
+
+                        for warning in size_warnings(&transaction, cli.max_inputs, cli.max_outputs)
+                        {
+                            report_warning(cli.format, warning);
+                        }
+
+                        if let Some(store) = &store {
+                            store.record_transaction(&transaction)?;
+                        }
+
+                        summary.record_transaction(&transaction);
+                        status_rows.push(batch_status_row(&cli, hash, Some(&transaction)));
+                        match cli.format {
+                            OutputFormat::Table => {
+                                show_transaction(
+                                    &cli,
+                                    &http,
+                                    fee_estimates_url.as_deref(),
+                                    &transaction,
+                                )
+                                .await?
+                            }
+                            OutputFormat::Json
+                            | OutputFormat::Yaml
+                            | OutputFormat::Messagepack
+                            | OutputFormat::Dot
+                            | OutputFormat::Html => transactions.push(transaction),
+                        }
+                    }
+                    Err(error) => {
+                        report_error(cli.format, transaction_error_message(hash, &*error));
+                        summary.record_failure();
+                        status_rows.push(batch_status_row(&cli, hash, None));
+
+                        if cli.fail_fast {
+                            std::process::exit(1);
+                        }
+                    }
+                }
+
+                if is_batch && cli.format == OutputFormat::Table {
+                    println!();
+                }
+            }
+            batch.finish();
+
+            match cli.format {
+                OutputFormat::Table if is_batch => {
+                    let status_table = status_rows.table().title(vec![
+                        "Hash".cell().bold(true),
+                        "Status".cell().bold(true),
+                        "Total Output".cell().bold(true),
+                        "Fee".cell().bold(true),
+                    ]);
+                    print_table(status_table)?;
+                    println!();
+
+                    summary.print_table(cli.btc_precision, cli.round)?
+                }
+                OutputFormat::Table => {}
+                OutputFormat::Json
+                | OutputFormat::Yaml
+                | OutputFormat::Messagepack
+                | OutputFormat::Html
+                    if is_batch =>
+                {
+                    print_result(
+                        &cli,
+                        &serde_json::json!({
+                            "transactions": transactions,
+                            "summary": summary,
+                        }),
+                    )?
+                }
+                OutputFormat::Json
+                | OutputFormat::Yaml
+                | OutputFormat::Messagepack
+                | OutputFormat::Html => {
+                    if let Some(transaction) = transactions.first() {
+                        print_result(&cli, transaction)?;
+                    }
+                }
+                OutputFormat::Dot => print!(
+                    "{}",
+                    transactions_to_dot(&transactions, cli.btc_precision, cli.round)
+                ),
+            }
+
+            std::process::exit(summary.exit_code());
+        }
+        Some("address") => {
+            if cli.inspect_address.is_empty() {
+                println!("{}", paint(Color::Cyan, "Provide a Bitcoin Addres (i.e -a bc1q9jk7j04lcjzdns6wjegeju78mxq27reg5e4wkycrs407yd0r07psvs8x7u)\n"));
+                help_panel();
+                std::process::exit(1);
+            }
+
+            // Catch the typos before making any request
+            for address in &cli.inspect_address {
+                if let Err(error) = validate_address(address, cli.testnet) {
+                    report_error(cli.format, format!("[!] {address}: {error}"));
+                    std::process::exit(1);
+                }
+            }
+
+            if let Some(seconds) = cli.watch {
+                if cli.inspect_address.len() > 1 {
+                    println!(
+                        "{}",
+                        paint(Color::Red, "[!] --watch follows a single address")
+                    );
+                    std::process::exit(1);
+                }
+
+                watch_address(
+                    &cli,
+                    &http,
+                    &cli.inspect_address[0],
+                    &inspect_address_url,
+                    bitcoin_price_url.as_deref(),
+                    &currency,
+                    Duration::from_secs(seconds),
+                )
+                .await?;
+            }
+
+            if cli.history && cli.inspect_address.len() > 1 {
+                report_error(
+                    cli.format,
+                    "[!] --history shows the history of a single address".to_string(),
+                );
+                std::process::exit(1);
+            }
+
+            // Several addresses are shown together, one row per address
+            if cli.inspect_address.len() > 1 {
+                let addresses_query = async {
+                    let progress = batch_progress(cli.inspect_address.len(), cli.quiet);
+                    let (progress_bar, http, inspect_address_url) =
+                        (&progress, &http, &inspect_address_url);
+                    let fetched: Vec<_> = futures::stream::iter(&cli.inspect_address)
+                        .map(|address| async move {
+                            let fetched = inspect_address(address, inspect_address_url, http).await;
+                            progress_bar.inc(1);
+                            (address, fetched)
+                        })
+                        .buffered(cli.concurrency.get())
+                        .collect()
+                        .await;
+                    progress.finish_and_clear();
+
+                    let mut bitcoin_addresses = Vec::new();
+                    let mut summary = BatchSummary::new(cli.inspect_address.len());
+                    for (address, fetched) in fetched {
+                        match fetched {
+                            Ok(bitcoin_address) => {
+                                bitcoin_addresses.push(bitcoin_address);
+                                summary.record_success();
+                            }
+                            Err(error) => {
+                                report_error(cli.format, address_error_message(address, &*error));
+                                summary.record_failure();
+
+                                if cli.fail_fast {
+                                    std::process::exit(1);
+                                }
+                            }
+                        }
+                    }
+
+                    (bitcoin_addresses, summary)
+                };
+
+                // The price is fetched while the addresses are, only the tables and the
+                // address reports show it
+                let ((mut bitcoin_addresses, summary), bitcoin_price) =
+                    tokio::join!(addresses_query, async {
+                        if cli.format == OutputFormat::Table || cli.summary {
+                            fetch_validated_price(
+                                &http,
+                                bitcoin_price_url.as_deref(),
+                                &currency,
+                                cli.format,
+                            )
+                            .await
+                        } else {
+                            Ok(None)
+                        }
+                    });
+
+                if let Some(sort) = cli.sort {
+                    if sort.sorts_unconfirmed() {
+                        report_error(
+                            cli.format,
+                            "[!] The addresses can only be sorted by balance, n-tx or received"
+                                .to_string(),
+                        );
+                        std::process::exit(1);
+                    }
+
+                    sort_addresses(&mut bitcoin_addresses, sort, cli.desc);
+                }
+
+                let quote = bitcoin_price?;
+                if let Some(quote) = &quote {
+                    check_price_age(&cli, quote, &currency);
+                }
+                let bitcoin_price = quote.map(|quote| quote.price);
+
+                if let Some(store) = &store {
+                    for bitcoin_address in &bitcoin_addresses {
+                        store.record_address(bitcoin_address)?;
+                    }
+                }
+
+                if cli.format != OutputFormat::Table {
+                    let addresses = if cli.summary {
+                        serde_json::to_value(
+                            bitcoin_addresses
+                                .iter()
+                                .map(|bitcoin_address| {
+                                    AddressReport::new(bitcoin_address, bitcoin_price, &currency)
+                                })
+                                .collect::<Vec<_>>(),
+                        )?
+                    } else {
+                        serde_json::to_value(&bitcoin_addresses)?
+                    };
+
+                    print_result(
+                        &cli,
+                        &serde_json::json!({
+                            "addresses": addresses,
+                            "summary": summary,
+                        }),
+                    )?;
+                    std::process::exit(summary.exit_code());
+                }
+
+                let table: Vec<_> = bitcoin_addresses
+                    .iter()
+                    .map(|bitcoin_address| {
                         let final_balance_btc =
                             bitcoin_address.final_balance as f64 / SATOSHIS_PER_BTC as f64;
-                        let final_balance_usd = final_balance_btc * bitcoin_price;
-                        vec![
+                        let mut row = vec![
+                            paint(
+                                Color::Cyan,
+                                shown_address(&bitcoin_address.address, cli.abbrev_addresses),
+                            )
+                            .cell(),
+                            paint(Color::Cyan, bitcoin_address.n_tx.to_string()).cell(),
+                            amount_cell(
+                                Color::Cyan,
+                                format_btc(
+                                    bitcoin_address.total_received,
+                                    cli.btc_precision,
+                                    cli.round,
+                                ),
+                            ),
+                            amount_cell(
+                                Color::Cyan,
+                                format_btc(
+                                    bitcoin_address.total_sent,
+                                    cli.btc_precision,
+                                    cli.round,
+                                ),
+                            ),
+                            balance_cell(&cli, bitcoin_address),
+                        ];
+
+                        if let Some(bitcoin_price) = bitcoin_price {
+                            row.push(amount_cell(
+                                Color::Cyan,
+                                format_fiat(
+                                    final_balance_btc * bitcoin_price,
+                                    &currency,
+                                    &number_format(),
+                                    cli.output_precision_usd,
+                                ),
+                            ));
+                        }
+
+                        row
+                    })
+                    .collect();
+
+                let mut title = vec![
+                    "Address".cell().bold(true),
+                    "Transactions Made".cell().bold(true),
+                    "Total Amount Received".cell().bold(true),
+                    "Total Amount Sent".cell().bold(true),
+                    "Balance".cell().bold(true),
+                ];
+                if bitcoin_price.is_some() {
+                    title.push(format!("Balance ({currency})").cell().bold(true));
+                }
+
+                let addresses_table = cli_table::Table::table(table)
+                    .title(title)
+                    .foreground_color(Some(cli_table::Color::Cyan));
+
+                if !cli.summary {
+                    print_table(addresses_table)?;
+                    println!();
+                }
+
+                // Summed in satoshis so the total doesn't drift, and converted only once
+                let grand_total: u64 = bitcoin_addresses
+                    .iter()
+                    .map(|bitcoin_address| bitcoin_address.final_balance)
+                    .sum();
+                let total_style = summary_style(Color::Cyan);
+                let mut grand_total_row = vec![
+                    paint(total_style, "Grand Total").cell(),
+                    amount_cell(
+                        total_style,
+                        format_btc(grand_total, cli.btc_precision, cli.round),
+                    ),
+                ];
+                if let Some(bitcoin_price) = bitcoin_price {
+                    grand_total_row.push(amount_cell(
+                        total_style,
+                        format_fiat(
+                            grand_total as f64 / SATOSHIS_PER_BTC as f64 * bitcoin_price,
+                            &currency,
+                            &number_format(),
+                            cli.output_precision_usd,
+                        ),
+                    ));
+                }
+
+                print_table(
+                    vec![grand_total_row]
+                        .table()
+                        .foreground_color(summary_border(Color::Cyan)),
+                )?;
+                if let Some(quote) = &quote {
+                    println!(
+                        "{} {}",
+                        paint(total_style, "Bitcoin price:"),
+                        price_label(&cli, quote, &currency)
+                    );
+                }
+                println!();
+
+                summary.print_table(cli.btc_precision, cli.round)?;
+
+                std::process::exit(summary.exit_code());
+            }
+
+            let (address_result, bitcoin_price) = tokio::join!(
+                inspect_address(&cli.inspect_address[0], &inspect_address_url, &http),
+                async {
+                    if cli.format == OutputFormat::Table || (cli.summary && !cli.history) {
+                        fetch_validated_price(
+                            &http,
+                            bitcoin_price_url.as_deref(),
+                            &currency,
+                            cli.format,
+                        )
+                        .await
+                    } else {
+                        Ok(None)
+                    }
+                }
+            );
+
+            if let (Ok(bitcoin_address), Some(store)) = (&address_result, &store) {
+                store.record_address(bitcoin_address)?;
+            }
+
+            if let Ok(Some(quote)) = &bitcoin_price {
+                check_price_age(&cli, quote, &currency);
+            }
+            let quote = bitcoin_price.as_ref().ok().copied().flatten();
+            let bitcoin_price = bitcoin_price.map(|quote| quote.map(|quote| quote.price));
+
+            match address_result {
+                Ok(bitcoin_address) if cli.format != OutputFormat::Table => {
+                    if cli.history {
+                        print_history(&cli, &http, &inspect_address_url, &bitcoin_address).await?;
+                    } else if cli.summary {
+                        print_result(
+                            &cli,
+                            &AddressReport::new(&bitcoin_address, bitcoin_price?, &currency),
+                        )?;
+                    } else {
+                        print_result(&cli, &bitcoin_address)?;
+                    }
+
+                    if let Some(path) = &cli.snapshot {
+                        save_snapshot(path, &bitcoin_address)?;
+                    }
+
+                    if let Some(path) = &cli.csv {
+                        write_address_csv(path, &bitcoin_address)?;
+                    }
+                }
+                // A fresh address is valid, it just hasn't been used. A diff or a
+                // snapshot still goes through the table
+                Ok(bitcoin_address)
+                    if bitcoin_address.n_tx == 0
+                        && cli.diff.is_none()
+                        && cli.snapshot.is_none() =>
+                {
+                    println!(
+                        "{}",
+                        paint(Color::Cyan, "This address has no transactions yet")
+                    );
+                }
+                Ok(bitcoin_address) => {
+                    let bitcoin_price = bitcoin_price?;
+
+                    // Value in fiat of an amount of satoshis, when the price is available
+                    let fiat = |satoshis: i64| {
+                        bitcoin_price.map(|bitcoin_price| {
+                            format_fiat(
+                                satoshis as f64 / SATOSHIS_PER_BTC as f64 * bitcoin_price,
+                                &currency,
+                                &number_format(),
+                                cli.output_precision_usd,
+                            )
+                        })
+                    };
+
+                    let bitcoin_address_table = if cli.summary {
+                        let mut row = vec![
+                            paint(Color::Cyan, &bitcoin_address.address).cell(),
+                            balance_cell(&cli, &bitcoin_address),
+                        ];
+                        let mut title =
+                            vec!["Address".cell().bold(true), "Balance".cell().bold(true)];
+                        if let Some(balance) = fiat(bitcoin_address.final_balance as i64) {
+                            row.push(amount_cell(Color::Cyan, balance));
+                            title.push(format!("Balance ({currency})").cell().bold(true));
+                        }
+
+                        vec![row]
+                            .table()
+                            .title(title)
+                            .foreground_color(Some(cli_table::Color::Cyan))
+                    } else {
+                        let mut rows = vec![vec![
+                            paint(Color::Cyan, bitcoin_address.n_tx.to_string()).cell(),
+                            amount_cell(
+                                Color::Cyan,
+                                format_btc(
+                                    bitcoin_address.total_received,
+                                    cli.btc_precision,
+                                    cli.round,
+                                ),
+                            ),
+                            amount_cell(
+                                Color::Cyan,
+                                format_btc(
+                                    bitcoin_address.total_sent,
+                                    cli.btc_precision,
+                                    cli.round,
+                                ),
+                            ),
+                            balance_cell(&cli, &bitcoin_address),
+                        ]];
+
+                        if let (Some(received), Some(sent), Some(balance)) = (
+                            fiat(bitcoin_address.total_received as i64),
+                            fiat(bitcoin_address.total_sent as i64),
+                            fiat(bitcoin_address.final_balance as i64),
+                        ) {
+                            rows.push(vec![
+                                " ".cell(),
+                                amount_cell(Color::Cyan, received),
+                                amount_cell(Color::Cyan, sent),
+                                amount_cell(Color::Cyan, balance),
+                            ]);
+                        }
+
+                        rows.extend([
                             vec![
-                                Color::Cyan.paint(bitcoin_address.n_tx.to_string()).cell(),
-                                Color::Cyan
-                                    .paint(format!("{} BTC", total_received_btc))
-                                    .cell(),
-                                Color::Cyan.paint(format!("{} BTC", total_sent_btc)).cell(),
-                                Color::Cyan
-                                    .paint(format!("{} BTC", final_balance_btc))
-                                    .cell(),
+                                "Address Type".cell().bold(true),
+                                paint(
+                                    Color::Cyan,
+                                    classify_address_type(&bitcoin_address.address).to_string(),
+                                )
+                                .cell(),
+                                " ".cell(),
+                                " ".cell(),
                             ],
                             vec![
+                                "First Seen".cell().bold(true),
+                                paint(
+                                    Color::Cyan,
+                                    match bitcoin_address.first_seen() {
+                                        Some((first_seen, true)) => {
+                                            first_seen.format("%Y-%m-%d %H:%M UTC").to_string()
+                                        }
+                                        Some((oldest, false)) => format!(
+                                            "before {}",
+                                            oldest.format("%Y-%m-%d %H:%M UTC")
+                                        ),
+                                        None => "N/A".to_string(),
+                                    },
+                                )
+                                .cell(),
+                                " ".cell(),
                                 " ".cell(),
-                                Color::Cyan
-                                    .paint(format!(
-                                        "${}{:.5}",
-                                        (total_received_usd.trunc() as u64)
-                                            .to_formatted_string(&Locale::en),
-                                        { total_received_usd.fract() }
-                                    ))
-                                    .cell(),
-                                Color::Cyan
-                                    .paint(format!(
-                                        "${}{:.5}",
-                                        (total_sent_usd.trunc() as u64)
-                                            .to_formatted_string(&Locale::en),
-                                        { total_sent_usd.fract() }
-                                    ))
-                                    .cell(),
-                                Color::Cyan
-                                    .paint(format!(
-                                        "${}{:.3}",
-                                        (final_balance_usd.trunc() as u64)
-                                            .to_formatted_string(&Locale::en),
-                                        { final_balance_usd.fract() }
-                                    ))
-                                    .cell(),
                             ],
-                        ]
-                        .table()
-                        .title(vec![
-                            "Transactions Made".cell().bold(true),
-                            "Total Amount Received".cell().bold(true),
-                            "Total Amount Sent".cell().bold(true),
-                            "Total Balance in the Account".cell().bold(true),
-                        ])
-                        .foreground_color(Some(cli_table::Color::Cyan))
+                            vec![
+                                "Last Seen".cell().bold(true),
+                                paint(
+                                    Color::Cyan,
+                                    bitcoin_address
+                                        .last_seen()
+                                        .map(|last_seen| {
+                                            last_seen.format("%Y-%m-%d %H:%M UTC").to_string()
+                                        })
+                                        .unwrap_or_else(|| "N/A".to_string()),
+                                )
+                                .cell(),
+                                " ".cell(),
+                                " ".cell(),
+                            ],
+                            // Received - Sent must give the balance, otherwise the API is inconsistent
+                            vec![
+                                "Received - Sent".cell().bold(true),
+                                paint(
+                                    Color::Cyan,
+                                    format!(
+                                        "{} - {} = {}",
+                                        format_btc(bitcoin_address.total_received, 8, cli.round),
+                                        format_btc(bitcoin_address.total_sent, 8, cli.round),
+                                        format_btc(
+                                            bitcoin_address.total_received as i64
+                                                - bitcoin_address.total_sent as i64,
+                                            8,
+                                            cli.round
+                                        ),
+                                    ),
+                                )
+                                .cell(),
+                                " ".cell(),
+                                if bitcoin_address.is_reconciled() {
+                                    paint(Color::Green, "✔ Matches the balance").cell()
+                                } else {
+                                    paint(
+                                        Color::Red.bold(),
+                                        "✘ Doesn't match the balance (API inconsistency)",
+                                    )
+                                    .cell()
+                                },
+                            ],
+                        ]);
+
+                        rows.table()
+                            .title(vec![
+                                "Transactions Made".cell().bold(true),
+                                "Total Amount Received".cell().bold(true),
+                                "Total Amount Sent".cell().bold(true),
+                                "Total Balance in the Account".cell().bold(true),
+                            ])
+                            .foreground_color(Some(cli_table::Color::Cyan))
                     };
 
-                    print_stdout(bitcoin_address_table)?;
+                    print_table(bitcoin_address_table)?;
+                    if let Some(quote) = &quote {
+                        println!(
+                            "{} {}",
+                            paint(summary_style(Color::Cyan), "Bitcoin price:"),
+                            price_label(&cli, quote, &currency)
+                        );
+                    }
                     println!();
+
+                    if cli.explain_address {
+                        println!(
+                            "{}\n",
+                            paint(
+                                Color::Cyan,
+                                explain_address(&cli, &bitcoin_address, bitcoin_price, &currency)
+                            )
+                        );
+                    }
+
+                    if let Some(path) = &cli.diff {
+                        let snapshot = load_snapshot(path)?;
+
+                        if snapshot.address.address != bitcoin_address.address {
+                            println!(
+                                "{}",
+                                paint(
+                                    Color::Red,
+                                    format!(
+                                        "[!] The snapshot {path} belongs to the address {}",
+                                        snapshot.address.address
+                                    )
+                                )
+                            );
+                            std::process::exit(1);
+                        }
+
+                        let diff = AddressDiff::between(&snapshot.address, &bitcoin_address);
+                        let btc = |satoshis: i64| {
+                            let btc = format_btc(satoshis, cli.btc_precision, cli.round);
+                            if satoshis > 0 {
+                                format!("+{btc}")
+                            } else {
+                                btc
+                            }
+                        };
+                        println!(
+                            "{}",
+                            paint(
+                                Color::Cyan,
+                                format!(
+                                    "Changes since {}",
+                                    snapshot.taken_at.format("%Y-%m-%d %H:%M:%S UTC")
+                                )
+                            )
+                        );
+
+                        let mut diff_rows = vec![vec![
+                            paint(Color::Cyan, format!("{:+}", diff.new_transactions)).cell(),
+                            amount_cell(Color::Cyan, btc(diff.received)),
+                            amount_cell(Color::Cyan, btc(diff.sent)),
+                            amount_cell(Color::Cyan, btc(diff.balance)),
+                        ]];
+
+                        if let (Some(received), Some(sent), Some(balance)) =
+                            (fiat(diff.received), fiat(diff.sent), fiat(diff.balance))
+                        {
+                            diff_rows.push(vec![
+                                " ".cell(),
+                                amount_cell(Color::Cyan, received),
+                                amount_cell(Color::Cyan, sent),
+                                amount_cell(Color::Cyan, balance),
+                            ]);
+                        }
+
+                        let diff_table = diff_rows
+                            .table()
+                            .title(vec![
+                                "New Transactions".cell().bold(true),
+                                "Received".cell().bold(true),
+                                "Sent".cell().bold(true),
+                                "Balance Change".cell().bold(true),
+                            ])
+                            .foreground_color(Some(cli_table::Color::Cyan));
+
+                        print_table(diff_table)?;
+                        println!();
+                    }
+
+                    if let Some(path) = &cli.snapshot {
+                        save_snapshot(path, &bitcoin_address)?;
+                        println!(
+                            "{}",
+                            paint(Color::Green, format!("[+] Snapshot saved to {path}"))
+                        );
+                    }
+
+                    if let Some(path) = &cli.csv {
+                        let written = write_address_csv(path, &bitcoin_address)?;
+                        let mut message = format!("[+] {written} transactions written to {path}");
+                        if (written as u64) < bitcoin_address.n_tx {
+                            message.push_str(&format!(
+                                " (the latest {written} of {})",
+                                bitcoin_address.n_tx
+                            ));
+                        }
+                        println!("{}", paint(Color::Green, message));
+                    }
+
+                    if cli.history {
+                        println!();
+                        print_history(&cli, &http, &inspect_address_url, &bitcoin_address).await?;
+                    }
+                }
+                Err(error) => {
+                    report_error(
+                        cli.format,
+                        address_error_message(&cli.inspect_address[0], &*error),
+                    );
+                    std::process::exit(1);
+                }
+            }
+        }
+        Some("decode") => {
+            let raw_hex = match &cli.hex {
+                Some(raw_hex) => raw_hex.clone(),
+                None => std::io::read_to_string(std::io::stdin())?,
+            };
+
+            match decode_raw_transaction(&raw_hex, cli.testnet) {
+                Ok(decoded) if cli.format != OutputFormat::Table => print_result(&cli, &decoded)?,
+                Ok(decoded) => show_decoded_transaction(&cli, &decoded)?,
+                Err(error) => {
+                    report_error(
+                        cli.format,
+                        format!("[!] Couldn't decode the raw transaction: {error}"),
+                    );
+                    std::process::exit(1);
+                }
+            }
+        }
+        Some("count") => {
+            let count = unconfirmed_count(&http, &unconfirmed_count_url).await?;
+
+            if cli.format != OutputFormat::Table {
+                print_result(&cli, &serde_json::json!({ "unconfirmed_count": count }))?;
+            } else if cli.quiet {
+                println!("{count}");
+            } else {
+                println!(
+                    "{} {}",
+                    paint(Color::Purple, "Unconfirmed transactions:"),
+                    paint(Color::Yellow, count.to_formatted_string(&number_format()))
+                );
+            }
+        }
+        Some("ping") => {
+            let started = Instant::now();
+
+            match block_count(&http, &block_count_url).await {
+                Ok(block_height) => {
+                    let latency_ms = started.elapsed().as_millis();
+
+                    if cli.format != OutputFormat::Table {
+                        print_result(
+                            &cli,
+                            &serde_json::json!({
+                                "api_base": api_base,
+                                "reachable": true,
+                                "latency_ms": latency_ms,
+                                "block_height": block_height,
+                            }),
+                        )?;
+                    } else {
+                        println!(
+                            "{}",
+                            paint(
+                                Color::Green,
+                                format!(
+                                    "[+] {api_base} is up, answered in {latency_ms} ms with the chain tip at block {}",
+                                    block_height.to_formatted_string(&number_format())
+                                )
+                            )
+                        );
+                    }
+                }
+                Err(error) => {
+                    report_error(
+                        cli.format,
+                        format!("[!] {api_base} isn't answering properly: {error}"),
+                    );
+                    std::process::exit(1);
                 }
-                Err(_) => println!(
+            }
+        }
+        Some("blocks") => {
+            if data_urls.is_empty() {
+                println!(
                     "{}",
-                    Color::Red.paint("[!] There is not transaction with the hash received")
-                ),
+                    paint(
+                        Color::Cyan,
+                        "Provide the range of blocks (i.e -e blocks --from 820000 --to 820009)\n"
+                    )
+                );
+                help_panel();
+                std::process::exit(1);
+            }
+
+            match fetch_blocks(&http, &data_urls, cli.concurrency.get()).await {
+                Ok(blocks) => print_blocks(&cli, &blocks)?,
+                Err(error) => {
+                    report_error(
+                        cli.format,
+                        format!("[!] Couldn't fetch the blocks: {error}"),
+                    );
+                    std::process::exit(1);
+                }
             }
         }
         _ => {
@@ -379,170 +2139,2112 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 fn help_panel() {
     println!(
         "{}",
-        Color::Red.paint("[!] Usage:  ./btcAnalyser -e [-n] [-i] [-a]")
+        paint(Color::Red, "[!] Usage:  ./btcAnalyser -e [-n] [-i] [-a]")
     );
-    println!("{}", Color::Red.paint("---------------------------------------------------------------------------------------------------"));
-    println!("\n\t{}", Color::Yellow.paint("[-e] Exploration Mode"));
+    println!("{}", paint(Color::Red, "---------------------------------------------------------------------------------------------------"));
+    println!("\n\t{}", paint(Color::Yellow, "[-e] Exploration Mode"));
     println!(
         "\t\t{}\t{}",
-        Color::Purple.paint("unconfirmed_transactions:"),
-        Color::Yellow.paint("List unconfirmed transactions.")
+        paint(Color::Purple, "unconfirmed_transactions:"),
+        paint(Color::Yellow, "List unconfirmed transactions.")
+    );
+    println!(
+        "\t\t{}\t\t\t{}",
+        paint(Color::Purple, "inspect:"),
+        paint(Color::Yellow, "Inspect a transaction hash.")
+    );
+    println!(
+        "\t\t{}\t\t\t{}",
+        paint(Color::Purple, "address:"),
+        paint(Color::Yellow, "Inspect a transaction address.")
+    );
+    println!(
+        "\t\t{}\t\t\t{}",
+        paint(Color::Purple, "count:"),
+        paint(Color::Yellow, "Number of unconfirmed transactions.")
+    );
+    println!(
+        "\t\t{}\t\t\t{}",
+        paint(Color::Purple, "auto:"),
+        paint(
+            Color::Yellow,
+            "Inspect a hash or an address (--input), whichever it is."
+        )
     );
     println!(
         "\t\t{}\t\t\t{}",
-        Color::Purple.paint("inspect:"),
-        Color::Yellow.paint("Inspect a transaction hash.")
+        paint(Color::Purple, "ping:"),
+        paint(
+            Color::Yellow,
+            "Check that the API is reachable and how fast it answers."
+        )
     );
     println!(
         "\t\t{}\t\t\t{}",
-        Color::Purple.paint("address:"),
-        Color::Yellow.paint("Inspect a transaction address.")
+        paint(Color::Purple, "blocks:"),
+        paint(
+            Color::Yellow,
+            "Fees and volume of a range of blocks (--from, --to)."
+        )
+    );
+    println!(
+        "\t\t{}\t\t\t{}",
+        paint(Color::Purple, "decode:"),
+        paint(
+            Color::Yellow,
+            "Decode a raw transaction (--hex or stdin) offline."
+        )
     );
     println!(
         "\n\t{}",
-        Color::Yellow.paint("[-n] Limit the number of outputs")
+        paint(Color::Yellow, "[-n] Limit the number of outputs")
     );
     println!(
         "\t\t{}\t{}",
-        Color::Purple.paint("Example:"),
-        Color::Yellow.paint("./btcAnalyser -e unconfirmed_transactions -n 10")
+        paint(Color::Purple, "Example:"),
+        paint(
+            Color::Yellow,
+            "./btcAnalyser -e unconfirmed_transactions -n 10"
+        )
     );
     println!(
         "\n\t{}",
-        Color::Yellow.paint("[-i] Provide the transaction hash")
+        paint(
+            Color::Yellow,
+            "[-i] Provide the transaction hash (several hashes separated by commas)"
+        )
     );
     println!(
         "\t\t{}\t{}",
-        Color::Purple.paint("Example:"),
-        Color::Yellow.paint("./btcAnalyser -e inspect -i 136937e5a742645ce873f079f8668aefdc2d06b8172e903d031a8bfb48969450")
+        paint(Color::Purple, "Example:"),
+        paint(Color::Yellow, "./btcAnalyser -e inspect -i 136937e5a742645ce873f079f8668aefdc2d06b8172e903d031a8bfb48969450")
     );
     println!(
         "\n\t{}",
-        Color::Yellow.paint("[-a] Provide the Bitcoin Address")
+        paint(
+            Color::Yellow,
+            "[-a] Provide the Bitcoin Address (several addresses separated by commas)"
+        )
     );
     println!(
         "\t\t{}\t{}",
-        Color::Purple.paint("Example:"),
-        Color::Yellow.paint("./btcAnalyser -e address -a bc1q9jk7j04lcjzdns6wjegeju78mxq27reg5e4wkycrs407yd0r07psvs8x7u")
+        paint(Color::Purple, "Example:"),
+        paint(Color::Yellow, "./btcAnalyser -e address -a bc1q9jk7j04lcjzdns6wjegeju78mxq27reg5e4wkycrs407yd0r07psvs8x7u")
     );
     println!();
 }
 
-async fn unconfirmed_transactions(
-    unconfirmed_transactions_url: &str,
-    bitcoin_price_url: &str,
-    number_outputs: usize,
-) -> Result<Vec<UndefinedTransaction>, Box<dyn std::error::Error>> {
-    // We make a http request to the APIs
-    let undefined_transaction_query = async {
-        reqwest::get(unconfirmed_transactions_url)
-            .await
-            .unwrap()
-            .text()
-            .await
-            .unwrap()
-    };
+/// Prints the totals, inputs and outputs tables of a transaction
+async fn show_transaction(
+    cli: &Cli,
+    http: &HttpClient,
+    fee_estimates_url: Option<&str>,
+    transaction: &Transaction,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if cli.simple {
+        match simple_payment(transaction) {
+            Some(payment) => {
+                println!(
+                    "{} {} {} {} {} {}\n",
+                    paint(Color::Purple, "From"),
+                    paint(Color::Green, shown_address(payment.from, cli.abbrev_addresses)),
+                    paint(Color::Purple, "→"),
+                    paint(Color::Green, shown_address(payment.to, cli.abbrev_addresses)),
+                    paint(Color::Purple, ":"),
+                    paint(
+                        Color::Yellow.bold(),
+                        format!(
+                            "{} (fee {})",
+                            format_btc(payment.amount, cli.btc_precision, cli.round),
+                            format_btc(payment.fee, cli.btc_precision, cli.round)
+                        )
+                    )
+                );
+                return Ok(());
+            }
+            None => println!(
+                "{}\n",
+                paint(
+                    Color::Cyan,
+                    "This isn't a simple payment from one address to another, showing the full tables"
+                )
+            ),
+        }
+    }
 
-    let bitcoin_price_query = async {
-        reqwest::get(bitcoin_price_url)
-            .await
-            .unwrap()
-            .text()
-            .await
-            .unwrap()
+    // Extract values from inputs[].prev_out.value
+    let total_inputs: Vec<&PrevOut> = transaction
+        .inputs
+        .iter()
+        .filter_map(|input| input.prev_out.as_ref())
+        .collect();
+
+    // Extract values from out[].value
+    let total_outputs: Vec<&Output> = transaction.out.iter().collect();
+
+    // A negative fee means the data we received doesn't make sense
+    let fee = match transaction.fee() {
+        _ if transaction.is_coinbase() => "N/A".to_string(),
+        Some(fee) => format_btc(fee, cli.btc_precision, cli.round),
+        None => {
+            println!(
+                "{}",
+                paint(Color::Red.bold(), format!(
+                    "[!] Warning: the outputs of the transaction {} are worth more than its inputs, the data looks inconsistent (malformed API response or parsing bug)\n",
+                    transaction.hash
+                ))
+            );
+            "N/A".to_string()
+        }
     };
 
-    // We get the response from the API, an we get a JSON string
-    let (undefined_transaction_json, bitcoin_price_json) =
-        tokio::join!(undefined_transaction_query, bitcoin_price_query);
+    // Show Total inputs table
+    let totals_table = vec![vec![
+        amount_cell(
+            Color::Yellow,
+            format_btc(transaction.total_input(), cli.btc_precision, cli.round),
+        ),
+        amount_cell(
+            Color::Yellow,
+            format_btc(transaction.total_output(), cli.btc_precision, cli.round),
+        ),
+        amount_cell(Color::Yellow, fee),
+        paint(Color::Yellow, fee_rate_label(transaction)).cell(),
+    ]]
+    .table()
+    .title(vec![
+        "Total Input".cell().bold(true),
+        "Total Output".cell().bold(true),
+        "Fee".cell().bold(true),
+        "Fee Rate".cell().bold(true),
+    ]);
+
+    print_table(totals_table.foreground_color(Some(cli_table::Color::Yellow)))?;
+
+    if cli.summary {
+        return Ok(());
+    }
+
+    // SegWit and Taproot inputs are cheaper to spend, it explains part of the fee
+    if !total_inputs.is_empty() {
+        let tally = ScriptTally::from_addresses(
+            total_inputs.iter().map(|prev_out| prev_out.addr.as_deref()),
+        );
+        println!(
+            "{} {}\n",
+            paint(Color::Purple, "Input types:"),
+            paint(Color::Yellow, tally.to_string())
+        );
+    }
+
+    let rate = transaction
+        .fee()
+        .filter(|_| !transaction.is_coinbase())
+        .and_then(|fee| fee_rate(fee, transaction.vsize()));
+    // Fetched once for both --explain-fee and --confirmation-eta
+    let wants_estimates =
+        cli.explain_fee || (cli.confirmation_eta && transaction.block_height.is_none());
+    let estimates = match (rate, fee_estimates_url) {
+        (Some(_), Some(fee_estimates_url)) if wants_estimates => {
+            Some(fetch_fee_estimates(http, fee_estimates_url).await?)
+        }
+        _ => None,
+    };
+
+    if cli.explain_fee {
+        match rate.zip(estimates.as_ref()) {
+            Some((rate, estimates)) => {
+                println!(
+                    "{} {}",
+                    paint(Color::Purple, "Fee rate:"),
+                    paint(
+                        Color::Yellow,
+                        format!(
+                            "{rate:.1} sat/vByte ({} sats / {} vBytes)",
+                            transaction.fee().unwrap_or(0),
+                            transaction.vsize()
+                        )
+                    )
+                );
+                println!(
+                    "{} {}",
+                    paint(Color::Purple, "Mempool rates:"),
+                    paint(
+                        Color::Yellow,
+                        format!(
+                            "minimum {} sat/vByte, regular {} sat/vByte, priority {} sat/vByte",
+                            estimates.limits.min, estimates.regular, estimates.priority
+                        )
+                    )
+                );
+                println!(
+                    "{} {}\n",
+                    paint(Color::Purple, "Verdict:"),
+                    paint(Color::Yellow.bold(), fee_verdict(rate, estimates))
+                );
+            }
+            None => println!(
+                "{}\n",
+                paint(
+                    Color::Cyan,
+                    "The fee rate of this transaction can't be computed"
+                )
+            ),
+        }
+    }
+
+    if cli.confirmation_eta {
+        let eta = match (transaction.block_height, rate.zip(estimates.as_ref())) {
+            (Some(block_height), _) => format!(
+                "already confirmed, in block {}",
+                block_height.to_formatted_string(&number_format())
+            ),
+            (None, Some((rate, estimates))) => {
+                confirmation_eta(confirmation_blocks(rate, estimates))
+            }
+            (None, None) => {
+                "unknown, the fee rate of this transaction can't be computed".to_string()
+            }
+        };
+
+        println!(
+            "{} {}\n",
+            paint(Color::Purple, "Estimated confirmation:"),
+            paint(Color::Yellow.bold(), eta)
+        );
+    }
+
+    if cli.flow {
+        print_flow(cli, transaction);
+    }
+
+    // With --only-address the tables keep the rows of that address, not the totals
+    if let Some(address) = &cli.only_address {
+        println!(
+            "{}\n",
+            paint(Color::Cyan, involvement(cli, transaction, address))
+        );
+    }
+    let is_shown = |addr: &Option<String>| {
+        cli.only_address
+            .as_ref()
+            .is_none_or(|address| addr.as_ref() == Some(address))
+    };
+    let total_inputs: Vec<&PrevOut> = total_inputs
+        .into_iter()
+        .filter(|prev_out| is_shown(&prev_out.addr))
+        .collect();
+
+    // Show Address inputs and its Value table
+    if transaction.is_coinbase() {
+        println!(
+            "{}",
+            paint(
+                Color::Cyan,
+                "This is a coinbase transaction, it has no inputs (it mints new coins)\n"
+            )
+        );
+    } else if !total_inputs.is_empty() {
+        let previewed_inputs = &total_inputs[..preview_len(total_inputs.len(), cli.first_n_inputs)];
+
+        let mut table: Vec<_> = previewed_inputs
+            .iter()
+            .take(row_limit(cli.max_rows_per_table))
+            .map(|prev_out| {
+                vec![
+                    paint(
+                        Color::Green,
+                        address_or_placeholder(&prev_out.addr, cli.abbrev_addresses),
+                    )
+                    .cell(),
+                    amount_cell(
+                        Color::Green,
+                        format_btc(prev_out.value, cli.btc_precision, cli.round),
+                    ),
+                    paint(
+                        Color::Green,
+                        prev_out
+                            .spends_from(cli.compact_hash)
+                            .unwrap_or_else(|| "N/A".to_string()),
+                    )
+                    .cell(),
+                ]
+            })
+            .collect();
+        table.extend(hidden_rows_notice(
+            previewed_inputs.len(),
+            cli.max_rows_per_table,
+        ));
+        table.extend(preview_notice(
+            total_inputs.len(),
+            previewed_inputs.len(),
+            "inputs",
+        ));
+        // The notices span the first two columns only
+        for row in &mut table {
+            row.resize_with(3, || " ".cell());
+        }
+
+        let table_inputs = cli_table::Table::table(table)
+            .title(vec![
+                "Address (input)".cell().bold(true),
+                "Value".cell().bold(true),
+                "Spends From".cell().bold(true),
+            ])
+            .foreground_color(Some(cli_table::Color::Green));
+
+        print_table(table_inputs)?;
+        println!();
+    }
+
+    // With --recipients-only the change is left out of the table, not of the totals
+    let recipients = transaction.recipients().filter(|_| cli.recipients_only);
+    if cli.recipients_only && recipients.is_none() && !total_outputs.is_empty() {
+        println!(
+            "{}",
+            paint(
+                Color::Cyan,
+                "No clear change output (none or all go back to the inputs), every output is shown"
+            )
+        );
+    }
+    let change_outputs = recipients
+        .as_ref()
+        .map_or(0, |recipients| total_outputs.len() - recipients.len());
+    let total_outputs: Vec<&Output> = recipients
+        .unwrap_or(total_outputs)
+        .into_iter()
+        .filter(|output| is_shown(&output.addr))
+        .collect();
+
+    if cli.group_outputs_by_value_bucket && !total_outputs.is_empty() {
+        let table: Vec<_> = value_buckets(&total_outputs)
+            .iter()
+            .map(|bucket| {
+                vec![
+                    paint(Color::Green, bucket.label).cell(),
+                    paint(
+                        Color::Green,
+                        bucket.count.to_formatted_string(&number_format()),
+                    )
+                    .cell(),
+                    amount_cell(
+                        Color::Green,
+                        format_btc(bucket.total, cli.btc_precision, cli.round),
+                    ),
+                ]
+            })
+            .collect();
+
+        print_table(
+            table
+                .table()
+                .title(vec![
+                    "Output Value".cell().bold(true),
+                    "Outputs".cell().bold(true),
+                    "Total".cell().bold(true),
+                ])
+                .foreground_color(Some(cli_table::Color::Green)),
+        )?;
+
+        return Ok(());
+    }
+
+    // Show Address Outputs and its Value table
+    if transaction.out.is_empty() {
+        println!(
+            "{}",
+            paint(Color::Cyan, "This transaction has no standard outputs")
+        );
+    } else if !total_outputs.is_empty() {
+        // Outputs under --min-output-btc are only left out of the table, not of the totals
+        let min_output = cli.min_output_btc.map(btc_to_satoshis).unwrap_or(0);
+        let previewed_outputs =
+            &total_outputs[..preview_len(total_outputs.len(), cli.first_n_outputs)];
+        let shown_outputs: Vec<&Output> = previewed_outputs
+            .iter()
+            .copied()
+            .filter(|output| output.value >= min_output)
+            .collect();
+
+        let mut table: Vec<_> = shown_outputs
+            .iter()
+            .take(row_limit(cli.max_rows_per_table))
+            .map(|output| {
+                let amount = format_btc(output.value, cli.btc_precision, cli.round);
+
+                // Marked with an arrow too, so it still stands out without colors
+                if is_large_output(output, cli.max_output_btc) {
+                    return vec![
+                        paint(
+                            Red.bold(),
+                            address_or_placeholder(&output.addr, cli.abbrev_addresses),
+                        )
+                        .cell(),
+                        amount_cell(Red.bold(), format!("▲ {amount}")),
+                    ];
+                }
+
+                vec![
+                    paint(
+                        Color::Green,
+                        address_or_placeholder(&output.addr, cli.abbrev_addresses),
+                    )
+                    .cell(),
+                    amount_cell(Color::Green, amount),
+                ]
+            })
+            .collect();
+        table.extend(hidden_rows_notice(
+            shown_outputs.len(),
+            cli.max_rows_per_table,
+        ));
+
+        let dust_outputs = previewed_outputs.len() - shown_outputs.len();
+        if dust_outputs > 0 {
+            table.push(vec![
+                paint(
+                    Color::Cyan,
+                    format!(
+                        "... {} outputs under {} hidden by --min-output-btc",
+                        dust_outputs.to_formatted_string(&number_format()),
+                        format_btc(min_output, 8, cli.round)
+                    ),
+                )
+                .cell(),
+                " ".cell(),
+            ]);
+        }
+        table.extend(preview_notice(
+            total_outputs.len(),
+            previewed_outputs.len(),
+            "outputs",
+        ));
+        if change_outputs > 0 {
+            table.push(vec![
+                paint(
+                    Color::Cyan,
+                    format!(
+                        "... {} change outputs back to the inputs hidden by --recipients-only",
+                        change_outputs.to_formatted_string(&number_format())
+                    ),
+                )
+                .cell(),
+                " ".cell(),
+            ]);
+        }
+
+        let table_outputs = cli_table::Table::table(table)
+            .title(vec![
+                "Address (output)".cell().bold(true),
+                "Value".cell().bold(true),
+            ])
+            .foreground_color(Some(cli_table::Color::Green));
+
+        print_table(table_outputs)?;
+    }
+
+    Ok(())
+}
+
+/// Blocks before confirming and the time they take, i.e "~2 blocks (~20 min)"
+fn confirmation_eta(blocks: Option<u64>) -> String {
+    let Some(blocks) = blocks else {
+        return "unlikely, the fee rate is below the minimum of the mempool".to_string();
+    };
+
+    let wait = format_age(chrono::Duration::minutes(
+        (blocks * BLOCK_INTERVAL_MINUTES) as i64,
+    ));
+    match blocks {
+        1 => format!("~1 block (~{wait})"),
+        blocks => format!("~{blocks} blocks (~{wait})"),
+    }
+}
+
+/// Tells whether `address` is a sender, a recipient or both in the transaction,
+/// with what it spent and received
+fn involvement(cli: &Cli, transaction: &Transaction, address: &str) -> String {
+    let spent: Vec<u64> = transaction
+        .inputs
+        .iter()
+        .filter_map(|input| input.prev_out.as_ref())
+        .filter(|prev_out| prev_out.addr.as_deref() == Some(address))
+        .map(|prev_out| prev_out.value)
+        .collect();
+    let received: Vec<u64> = transaction
+        .out
+        .iter()
+        .filter(|output| output.addr.as_deref() == Some(address))
+        .map(|output| output.value)
+        .collect();
+
+    let amount =
+        |values: &[u64]| format_btc(values.iter().sum::<u64>(), cli.btc_precision, cli.round);
+    let address = shown_address(address, cli.abbrev_addresses);
+
+    match (spent.is_empty(), received.is_empty()) {
+        (true, true) => format!("{address} isn't involved in this transaction"),
+        (false, true) => format!("{address} is a sender, it spent {}", amount(&spent)),
+        (true, false) => format!(
+            "{address} is a recipient, it received {}",
+            amount(&received)
+        ),
+        (false, false) => format!(
+            "{address} is both a sender and a recipient, it spent {} and received {}",
+            amount(&spent),
+            amount(&received)
+        ),
+    }
+}
+
+/// Prints the tables of a transaction decoded offline, the inputs only tell which
+/// output they spend so there are no input totals nor fee
+fn show_decoded_transaction(
+    cli: &Cli,
+    decoded: &DecodedTransaction,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let totals_table = vec![vec![
+        paint(Color::Yellow, decoded.txid.clone()).cell(),
+        amount_cell(
+            Color::Yellow,
+            format_btc(decoded.total_output(), cli.btc_precision, cli.round),
+        ),
+        paint(Color::Yellow, format!("{} vBytes", decoded.vsize)).cell(),
+    ]]
+    .table()
+    .title(vec![
+        "Txid".cell().bold(true),
+        "Total Output".cell().bold(true),
+        "Size".cell().bold(true),
+    ]);
+
+    print_table(totals_table.foreground_color(Some(cli_table::Color::Yellow)))?;
+    println!();
+
+    if cli.summary {
+        return Ok(());
+    }
+
+    let mut table: Vec<_> = decoded
+        .inputs
+        .iter()
+        .take(row_limit(cli.max_rows_per_table))
+        .map(|input| {
+            vec![
+                paint(Color::Green, input.outpoint.clone()).cell(),
+                paint(Color::Green, if input.has_witness { "yes" } else { "no" }).cell(),
+            ]
+        })
+        .collect();
+    table.extend(hidden_rows_notice(
+        decoded.inputs.len(),
+        cli.max_rows_per_table,
+    ));
+
+    let table_inputs = cli_table::Table::table(table)
+        .title(vec![
+            "Spent Output (input)".cell().bold(true),
+            "Witness".cell().bold(true),
+        ])
+        .foreground_color(Some(cli_table::Color::Green));
+
+    print_table(table_inputs)?;
+    println!();
+
+    let mut table: Vec<_> = decoded
+        .out
+        .iter()
+        .take(row_limit(cli.max_rows_per_table))
+        .map(|output| {
+            vec![
+                paint(
+                    Color::Green,
+                    address_or_placeholder(&output.addr, cli.abbrev_addresses),
+                )
+                .cell(),
+                amount_cell(
+                    Color::Green,
+                    format_btc(output.value, cli.btc_precision, cli.round),
+                ),
+            ]
+        })
+        .collect();
+    table.extend(hidden_rows_notice(
+        decoded.out.len(),
+        cli.max_rows_per_table,
+    ));
+
+    let table_outputs = cli_table::Table::table(table)
+        .title(vec![
+            "Address (output)".cell().bold(true),
+            "Value".cell().bold(true),
+        ])
+        .foreground_color(Some(cli_table::Color::Green));
+
+    print_table(table_outputs)?;
+
+    Ok(())
+}
+
+/// Balance of an address for the tables: highlighted when it still holds funds,
+/// grayed out when it's empty and told apart as fully spent when it received some
+fn balance_cell(cli: &Cli, bitcoin_address: &BitcoinAddress) -> CellStruct {
+    let balance = format_btc(bitcoin_address.final_balance, cli.btc_precision, cli.round);
+
+    match AddressStatus::of(bitcoin_address) {
+        AddressStatus::Unused => amount_cell(Color::Fixed(244), balance),
+        AddressStatus::FullySpent => {
+            amount_cell(Color::Fixed(244), format!("{balance} (empty, fully spent)"))
+        }
+        AddressStatus::Funded => amount_cell(Color::Green.bold(), balance),
+    }
+}
+
+/// Row of the status table of a batch of hashes, green when the transaction is
+/// confirmed, yellow while it's unconfirmed and red when it couldn't be fetched
+fn batch_status_row(cli: &Cli, hash: &str, transaction: Option<&Transaction>) -> Vec<CellStruct> {
+    let hash = shown_hash(cli, hash);
+
+    let Some(transaction) = transaction else {
+        return vec![
+            paint(Color::Red, hash).cell(),
+            paint(Color::Red.bold(), "✘ Not found").cell(),
+            " ".cell(),
+            " ".cell(),
+        ];
+    };
+
+    let (color, status) = match transaction.block_height {
+        Some(block_height) => (
+            Color::Green,
+            format!(
+                "✔ Confirmed (block {})",
+                block_height.to_formatted_string(&number_format())
+            ),
+        ),
+        None => (Color::Yellow, "⧗ Unconfirmed".to_string()),
+    };
+    let fee = match transaction.fee() {
+        Some(fee) if !transaction.is_coinbase() => format_btc(fee, cli.btc_precision, cli.round),
+        _ => "N/A".to_string(),
+    };
+
+    vec![
+        paint(color, hash).cell(),
+        paint(color.bold(), status).cell(),
+        amount_cell(
+            color,
+            format_btc(transaction.total_output(), cli.btc_precision, cli.round),
+        ),
+        amount_cell(color, fee),
+    ]
+}
+
+/// Prints an error, on stderr when the output is structured so it stays parseable
+fn report_error(format: OutputFormat, message: String) {
+    match format {
+        OutputFormat::Table => println!("{}", paint(Color::Red, message)),
+        _ => eprintln!("{message}"),
+    }
+}
+
+/// Prints a result in JSON, YAML or MessagePack, reduced to the --fields when there are some
+fn print_result(cli: &Cli, value: &impl Serialize) -> Result<(), Box<dyn std::error::Error>> {
+    let mut value = serde_json::to_value(value)?;
+    if cli.include_raw_values {
+        value = include_raw_values(value);
+    }
+    let value = select_fields(value, &cli.fields);
+    match cli.format {
+        OutputFormat::Yaml => print!("{}", serde_yaml::to_string(&value)?),
+        OutputFormat::Messagepack => write_messagepack(&value)?,
+        OutputFormat::Html => write_html(cli, &value),
+        _ if cli.json_compact => println!("{}", serde_json::to_string(&value)?),
+        _ => println!("{}", serde_json::to_string_pretty(&value)?),
+    }
+
+    Ok(())
+}
+
+/// Prints a value as an HTML table, headed in the color of the mode
+fn write_html(cli: &Cli, value: &Value) {
+    let accent = match cli.exploration_mode.as_deref() {
+        Some("unconfirmed_transactions") => "#b58900",
+        Some("address") => "#00838f",
+        Some("blocks") => "#1565c0",
+        _ => "#2e7d32",
+    };
+
+    print!("{}", to_html(value, accent));
+}
+
+/// Writes a value as MessagePack on stdout, the maps keep their keys
+fn write_messagepack(value: &Value) -> Result<(), Box<dyn std::error::Error>> {
+    let mut stdout = std::io::stdout().lock();
+    stdout.write_all(&rmp_serde::to_vec_named(value)?)?;
+    stdout.flush()?;
+
+    Ok(())
+}
+
+/// Prints a warning, on stderr when the output is structured so it stays parseable
+fn report_warning(format: OutputFormat, message: String) {
+    match format {
+        OutputFormat::Table => println!("{}", paint(Color::Yellow, message)),
+        _ => eprintln!("{message}"),
+    }
+}
+
+/// Warnings for a transaction with an abnormal number of inputs or outputs, a
+/// limit of 0 disables the check
+fn size_warnings(transaction: &Transaction, max_inputs: usize, max_outputs: usize) -> Vec<String> {
+    let mut warnings = Vec::new();
+
+    let inputs = transaction.inputs.len();
+    if max_inputs > 0 && inputs > max_inputs {
+        warnings.push(format!(
+            "[!] This transaction has {} inputs — likely a consolidation",
+            inputs.to_formatted_string(&number_format())
+        ));
+    }
+
+    let outputs = transaction.out.len();
+    if max_outputs > 0 && outputs > max_outputs {
+        warnings.push(format!(
+            "[!] This transaction has {} outputs — likely a batch of payouts",
+            outputs.to_formatted_string(&number_format())
+        ));
+    }
+
+    warnings
+}
+
+/// A transaction hash is 32 bytes written as 64 hex characters
+fn is_transaction_hash(value: &str) -> bool {
+    value.len() == 64 && value.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+/// Parses a BTC amount given on the command line, it can't be negative
+fn parse_btc_amount(value: &str) -> Result<f64, String> {
+    match value.parse::<f64>() {
+        Ok(btc) if btc.is_finite() && btc >= 0.0 => Ok(btc),
+        _ => Err(format!("{value} isn't a valid amount of BTC")),
+    }
+}
+
+/// Checks a --time-format, chrono can't render an invalid one
+fn parse_time_format(value: &str) -> Result<String, String> {
+    if StrftimeItems::new(value).any(|item| item == Item::Error) {
+        return Err(format!("{value} isn't a valid strftime format"));
+    }
+
+    Ok(value.to_string())
+}
+
+/// Format of the times in the unconfirmed table, from --time-format, --12h and
+/// --precise-time
+fn time_format(cli: &Cli) -> &str {
+    match &cli.time_format {
+        Some(time_format) => time_format,
+        None if cli.twelve_hour && cli.precise_time => "%I:%M:%S %p",
+        None if cli.twelve_hour => "%I:%M %p",
+        None if cli.precise_time => "%H:%M:%S",
+        None => "%H:%M",
+    }
+}
+
+fn btc_to_satoshis(btc: f64) -> u64 {
+    (btc * SATOSHIS_PER_BTC as f64).round() as u64
+}
+
+/// Fee rate of the transaction per vByte and per weight unit, N/A for a coinbase
+/// or when the size of the transaction is unknown
+fn fee_rate_label(transaction: &Transaction) -> String {
+    if transaction.is_coinbase() {
+        return "N/A".to_string();
+    }
+
+    let Some(fee) = transaction.fee() else {
+        return "N/A".to_string();
+    };
+    if fee == 0 {
+        return "0 sat/vB".to_string();
+    }
+
+    match (
+        fee_rate(fee, transaction.vsize()),
+        fee_rate(fee, transaction.weight_units()),
+    ) {
+        (Some(per_vbyte), Some(per_weight_unit)) => {
+            format!("{per_vbyte:.1} sat/vB ({per_weight_unit:.1} sat/WU)")
+        }
+        _ => "N/A".to_string(),
+    }
+}
+
+/// Address of the input spending the most, taken as the one sending the transaction
+fn primary_sender(inputs: &[Input]) -> Option<String> {
+    inputs
+        .iter()
+        .filter_map(|input| input.prev_out.as_ref())
+        .filter(|prev_out| prev_out.addr.is_some())
+        .max_by_key(|prev_out| prev_out.value)?
+        .addr
+        .clone()
+}
+
+/// Fills the sender of the unconfirmed transactions the feed gave without their
+/// input addresses, from the full transactions. The ones that can't be fetched are
+/// left without
+async fn resolve_senders(
+    cli: &Cli,
+    undefined_transactions: &mut [UndefinedTransaction],
+    inspect_transaction_url: &str,
+    http: &HttpClient,
+) {
+    let missing: Vec<String> = undefined_transactions
+        .iter()
+        .filter(|undefined_transaction| undefined_transaction.sender.is_none())
+        .map(|undefined_transaction| undefined_transaction.hash.clone())
+        .collect();
+    if missing.is_empty() {
+        return;
+    }
+
+    let fetched = fetch_transactions(
+        &missing,
+        inspect_transaction_url,
+        http,
+        cli.concurrency,
+        cli.quiet,
+    )
+    .await;
+
+    for undefined_transaction in undefined_transactions {
+        if let Some(Ok((transaction, _))) = fetched.get(&undefined_transaction.hash) {
+            undefined_transaction.sender = primary_sender(&transaction.inputs);
+        }
+    }
+}
+
+/// A payment from a single address to another one, with the change going back
+struct SimplePayment<'a> {
+    from: &'a str,
+    to: &'a str,
+    amount: u64,
+    fee: u64,
+}
+
+/// Recognizes the everyday payment: every input spends from the same address and
+/// there are at most two outputs, the payment and the change back to the sender.
+///
+/// The recipient is the largest output not paying back to the sender, so with two
+/// outputs to other addresses the smaller one is taken as change to a new address.
+fn simple_payment(transaction: &Transaction) -> Option<SimplePayment<'_>> {
+    if transaction.is_coinbase() || transaction.out.len() > 2 {
+        return None;
+    }
+
+    let mut input_addresses = transaction
+        .inputs
+        .iter()
+        .map(|input| input.prev_out.as_ref()?.addr.as_deref());
+    let from = input_addresses.next()??;
+    if !input_addresses.all(|addr| addr == Some(from)) {
+        return None;
+    }
+
+    let payment = transaction
+        .out
+        .iter()
+        .filter(|output| output.addr.as_deref() != Some(from))
+        .max_by_key(|output| output.value)?;
+
+    Some(SimplePayment {
+        from,
+        to: payment.addr.as_deref()?,
+        amount: payment.value,
+        fee: transaction.fee()?,
+    })
+}
+
+/// Address shown in the tables, abbreviated with --abbrev-addresses, non-standard
+/// scripts have none
+fn address_or_placeholder(addr: &Option<String>, abbrev_addresses: usize) -> String {
+    match addr {
+        Some(addr) => shown_address(addr, abbrev_addresses),
+        None => "(no address, non-standard script)".to_string(),
+    }
+}
+
+/// An address as the tables show it, abbreviated with --abbrev-addresses and
+/// linked with --links
+fn shown_address(address: &str, abbrev_addresses: usize) -> String {
+    link(
+        Linked::Address,
+        address,
+        abbreviate(address, abbrev_addresses),
+    )
+}
+
+/// A transaction hash as the tables show it, compacted with --compact-hash and
+/// linked with --links
+fn shown_hash(cli: &Cli, hash: &str) -> String {
+    let text = if cli.compact_hash {
+        compact_hash(hash)
+    } else {
+        hash.to_string()
+    };
+
+    link(Linked::Transaction, hash, text)
+}
+
+/// Hash of an unconfirmed transaction as its table shows it, followed by an alert
+/// when the feed flags it as a double spend
+fn unconfirmed_hash_label(cli: &Cli, undefined_transaction: &UndefinedTransaction) -> String {
+    let hash = shown_hash(cli, &undefined_transaction.hash);
+    if undefined_transaction.double_spend {
+        format!("{hash} ⚠ double-spend")
+    } else {
+        hash
+    }
+}
+
+/// Color of the row of an unconfirmed transaction, red for a double spend: a
+/// conflicting spend was seen, the zero-conf payment may never arrive
+fn unconfirmed_row_color(undefined_transaction: &UndefinedTransaction) -> Color {
+    if undefined_transaction.double_spend {
+        Color::Red
+    } else {
+        Color::Yellow
+    }
+}
+
+/// Table of the unconfirmed transactions paying the highest fee rates, with --top-fee
+fn top_fee_table(cli: &Cli, top_transactions: &[UndefinedTransaction]) -> TableStruct {
+    let table: Vec<_> = top_transactions
+        .iter()
+        .map(|undefined_transaction| {
+            let color = unconfirmed_row_color(undefined_transaction);
+            let fee_rate = undefined_transaction
+                .fee_rate
+                .map(|fee_rate| format!("{fee_rate:.1} sat/vByte"))
+                .unwrap_or_else(|| "N/A".to_string());
+
+            let mut row = vec![
+                paint(color, unconfirmed_hash_label(cli, undefined_transaction)).cell(),
+                paint(color.bold(), fee_rate).cell(),
+                amount_cell(
+                    color,
+                    format_btc(
+                        undefined_transaction.amount_satoshis,
+                        cli.btc_precision,
+                        cli.round,
+                    ),
+                ),
+                paint(color, undefined_transaction.time.format(time_format(cli))).cell(),
+            ];
+            if cli.show_senders {
+                row.insert(
+                    1,
+                    paint(color, sender_label(cli, undefined_transaction)).cell(),
+                );
+            }
+
+            row
+        })
+        .collect();
+
+    let mut title = vec![
+        "Hash".cell().bold(true),
+        "Fee Rate".cell().bold(true),
+        "Bitcoin".cell().bold(true),
+        "Time".cell().bold(true),
+    ];
+    if cli.show_senders {
+        title.insert(1, "From".cell().bold(true));
+    }
+
+    table
+        .table()
+        .title(title)
+        .foreground_color(Some(cli_table::Color::Yellow))
+}
+
+/// Sender of an unconfirmed transaction as the From column shows it
+fn sender_label(cli: &Cli, undefined_transaction: &UndefinedTransaction) -> String {
+    undefined_transaction
+        .sender
+        .as_deref()
+        .map(|sender| shown_address(sender, cli.abbrev_addresses))
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+/// Number of rows a table shows with --max-rows-per-table, where 0 means all of them
+fn row_limit(max_rows: usize) -> usize {
+    if max_rows == 0 {
+        usize::MAX
+    } else {
+        max_rows
+    }
+}
+
+/// Last row of a table truncated by --max-rows-per-table, telling how many rows are hidden
+fn hidden_rows_notice(total_rows: usize, max_rows: usize) -> Option<Vec<CellStruct>> {
+    let hidden = total_rows.saturating_sub(row_limit(max_rows));
+    if hidden == 0 {
+        return None;
+    }
+
+    Some(vec![
+        paint(
+            Color::Cyan,
+            format!(
+                "... and {} more (use --max-rows-per-table 0 for all)",
+                hidden.to_formatted_string(&number_format())
+            ),
+        )
+        .cell(),
+        " ".cell(),
+    ])
+}
+
+/// Outputs of a value range, with their sum in satoshis
+struct ValueBucket {
+    label: &'static str,
+    count: usize,
+    total: u64,
+}
+
+/// Upper bounds, in satoshis, of the value ranges of --group-outputs-by-value-bucket
+/// and their labels, the last range has none
+const VALUE_BUCKETS: [(Option<u64>, &str); 4] = [
+    (Some(100_000), "< 0.001 BTC"),
+    (Some(1_000_000), "0.001 – 0.01 BTC"),
+    (Some(10_000_000), "0.01 – 0.1 BTC"),
+    (None, "≥ 0.1 BTC"),
+];
+
+/// Counts and sums the outputs per value range, the empty ranges included
+fn value_buckets(outputs: &[&Output]) -> Vec<ValueBucket> {
+    let mut buckets: Vec<ValueBucket> = VALUE_BUCKETS
+        .iter()
+        .map(|&(_, label)| ValueBucket {
+            label,
+            count: 0,
+            total: 0,
+        })
+        .collect();
+
+    for output in outputs {
+        let index = VALUE_BUCKETS
+            .iter()
+            .position(|(bound, _)| bound.is_none_or(|bound| output.value < bound))
+            .unwrap_or(VALUE_BUCKETS.len() - 1);
+        buckets[index].count += 1;
+        buckets[index].total += output.value;
+    }
+
+    buckets
+}
+
+/// Whether an output is worth more than --max-output-btc
+fn is_large_output(output: &Output, max_output_btc: Option<f64>) -> bool {
+    max_output_btc.is_some_and(|max_output_btc| output.value > btc_to_satoshis(max_output_btc))
+}
+
+/// Number of inputs or outputs kept by a --first-n-inputs/--first-n-outputs preview
+fn preview_len(total: usize, first_n: Option<usize>) -> usize {
+    first_n.map_or(total, |first_n| first_n.min(total))
+}
+
+/// Last row of a table previewed with --first-n-inputs/--first-n-outputs, telling how
+/// many of the `kind` were left out of the preview
+fn preview_notice(total: usize, previewed: usize, kind: &str) -> Option<Vec<CellStruct>> {
+    let left_out = total - previewed;
+    if left_out == 0 {
+        return None;
+    }
+
+    Some(vec![
+        paint(
+            Color::Cyan,
+            format!(
+                "... {} more {kind} after the first {}, counted in the totals",
+                left_out.to_formatted_string(&number_format()),
+                previewed.to_formatted_string(&number_format())
+            ),
+        )
+        .cell(),
+        " ".cell(),
+    ])
+}
+
+/// Keeps the first `head` or the last `tail` rows, or all of them when none is given
+fn select_rows<T>(mut rows: Vec<T>, head: Option<usize>, tail: Option<usize>) -> Vec<T> {
+    if let Some(head) = head {
+        rows.truncate(head);
+    }
+
+    if let Some(tail) = tail {
+        let skip = rows.len().saturating_sub(tail);
+        rows.drain(..skip);
+    }
+
+    rows
+}
+
+/// Sorts the addresses by `key`, ties are ordered by the address itself so the
+/// output is always the same
+fn sort_addresses(bitcoin_addresses: &mut [BitcoinAddress], key: SortKey, desc: bool) {
+    bitcoin_addresses.sort_by(|a, b| {
+        let ordering = match key {
+            SortKey::Balance => a.final_balance.cmp(&b.final_balance),
+            SortKey::NTx => a.n_tx.cmp(&b.n_tx),
+            SortKey::Received => a.total_received.cmp(&b.total_received),
+            SortKey::Amount | SortKey::Time => std::cmp::Ordering::Equal,
+        };
+        let ordering = if desc { ordering.reverse() } else { ordering };
+
+        ordering.then_with(|| a.address.cmp(&b.address))
+    });
+}
+
+/// Sorts the unconfirmed transactions by `key`, ties are ordered by arrival time
+/// and then by hash so the same feed always gives the same table
+fn sort_unconfirmed(undefined_transactions: &mut [UndefinedTransaction], key: SortKey, desc: bool) {
+    undefined_transactions.sort_by(|a, b| {
+        let ordering = match key {
+            SortKey::Amount => a.amount_satoshis.cmp(&b.amount_satoshis),
+            SortKey::Time => a.time.cmp(&b.time),
+            SortKey::Balance | SortKey::NTx | SortKey::Received => std::cmp::Ordering::Equal,
+        };
+        let ordering = if desc { ordering.reverse() } else { ordering };
+
+        ordering
+            .then_with(|| a.time.cmp(&b.time))
+            .then_with(|| a.hash.cmp(&b.hash))
+    });
+}
+
+/// The USD price comes from the stats endpoint, any other currency from the ticker
+fn bitcoin_price_url(api_base: &str, currency: &str) -> String {
+    if currency == "USD" {
+        format!("{api_base}/stats?format=json")
+    } else {
+        format!("{api_base}/ticker")
+    }
+}
+
+/// Reads the price of one bitcoin in `currency` from the JSON of `bitcoin_price_url`.
+///
+/// The layout of the price has changed across the endpoints and their versions, so
+/// the stats layout (`market_price_usd`) is tried first and then the ticker one
+/// (`{"USD": {"last": ..., "15m": ...}}`)
+fn parse_bitcoin_price(
+    bitcoin_price_json: &str,
+    currency: &str,
+) -> Result<f64, Box<dyn std::error::Error>> {
+    let parsed: Value = serde_json::from_str(bitcoin_price_json)?;
+
+    let bitcoin_price = parsed[format!("market_price_{}", currency.to_lowercase())]
+        .as_f64()
+        .or_else(|| parsed[currency]["last"].as_f64())
+        .or_else(|| parsed[currency]["15m"].as_f64());
+
+    bitcoin_price
+        .ok_or_else(|| format!("The API doesn't provide the bitcoin price in {currency}").into())
+}
+
+/// An API glitch can give a price of 0 or worse, in that case the price is
+/// discarded with a warning so no misleading fiat amounts are shown. The warning
+/// goes to stderr with the structured formats, to keep their output parseable
+fn validate_price(bitcoin_price: f64, currency: &str, format: OutputFormat) -> Option<f64> {
+    if bitcoin_price.is_finite() && bitcoin_price > 0.0 {
+        return Some(bitcoin_price);
+    }
+
+    report_warning(
+        format,
+        format!(
+            "[!] The API returned an invalid bitcoin price ({bitcoin_price}), the {currency} amounts are not shown\n"
+        ),
+    );
+
+    None
+}
+
+/// Price of a bitcoin when there is a price to fetch, `None` without one or when
+/// the API gives a nonsensical price
+async fn fetch_validated_price(
+    http: &HttpClient,
+    bitcoin_price_url: Option<&str>,
+    currency: &str,
+    format: OutputFormat,
+) -> Result<Option<PriceQuote>, Box<dyn std::error::Error>> {
+    match bitcoin_price_url {
+        Some(bitcoin_price_url) => {
+            PriceQuote::parse(&http.fetch_text(bitcoin_price_url).await?, currency, format)
+        }
+        None => Ok(None),
+    }
+}
+
+/// A validated bitcoin price and when the API computed it, only the stats endpoint
+/// tells the time
+#[derive(Debug, Clone, Copy)]
+struct PriceQuote {
+    price: f64,
+    timestamp: Option<DateTime<Utc>>,
+}
+
+impl PriceQuote {
+    fn parse(
+        bitcoin_price_json: &str,
+        currency: &str,
+        format: OutputFormat,
+    ) -> Result<Option<PriceQuote>, Box<dyn std::error::Error>> {
+        let price = validate_price(
+            parse_bitcoin_price(bitcoin_price_json, currency)?,
+            currency,
+            format,
+        );
+
+        Ok(price.map(|price| PriceQuote {
+            price,
+            timestamp: parse_price_timestamp(bitcoin_price_json),
+        }))
+    }
+
+    fn age(&self) -> Option<chrono::Duration> {
+        self.timestamp.map(|timestamp| Utc::now() - timestamp)
+    }
+}
+
+/// When the price of the stats endpoint was computed, its `timestamp` is in milliseconds
+fn parse_price_timestamp(bitcoin_price_json: &str) -> Option<DateTime<Utc>> {
+    let parsed: Value = serde_json::from_str(bitcoin_price_json).ok()?;
+
+    DateTime::from_timestamp_millis(parsed["timestamp"].as_f64()? as i64)
+}
+
+/// Age of a price in the unit that reads best, i.e `45s`, `12 min` or `3h 05min`
+fn format_age(age: chrono::Duration) -> String {
+    let seconds = age.num_seconds().max(0);
+    match seconds {
+        0..=59 => format!("{seconds}s"),
+        60..=3599 => format!("{} min", seconds / 60),
+        _ => format!("{}h {:02}min", seconds / 3600, seconds % 3600 / 60),
+    }
+}
+
+/// Warns with --max-price-age when the price is older than allowed, the fiat
+/// amounts are still shown
+fn check_price_age(cli: &Cli, quote: &PriceQuote, currency: &str) {
+    let (Some(max_price_age), Some(age)) = (cli.max_price_age, quote.age()) else {
+        return;
+    };
+
+    if age.num_seconds() > max_price_age as i64 {
+        report_warning(
+            cli.format,
+            format!(
+                "[!] The bitcoin price is {} old, the {currency} amounts may be out of date\n",
+                format_age(age)
+            ),
+        );
+    }
+}
+
+/// Price shown in the footers, with its age when the API tells it
+fn price_label(cli: &Cli, quote: &PriceQuote, currency: &str) -> String {
+    let price = format_fiat(
+        quote.price,
+        currency,
+        &number_format(),
+        cli.output_precision_usd,
+    );
+
+    match quote.age() {
+        Some(age) => format!("{price} ({} old)", format_age(age)),
+        None => price,
+    }
+}
+
+async fn unconfirmed_transactions(
+    http: &HttpClient,
+    unconfirmed_transactions_url: &str,
+    bitcoin_price_url: Option<&str>,
+    currency: &str,
+    number_outputs: usize,
+    exclude_change: bool,
+    format: OutputFormat,
+) -> Result<(Vec<UndefinedTransaction>, Option<PriceQuote>), Box<dyn std::error::Error>> {
+    // Both requests are made at the same time
+    let bitcoin_price_query = async {
+        match bitcoin_price_url {
+            Some(bitcoin_price_url) => http.fetch_text(bitcoin_price_url).await.map(Some),
+            None => Ok(None),
+        }
+    };
+
+    let (undefined_transaction_json, bitcoin_price_json) = tokio::try_join!(
+        http.fetch_text(unconfirmed_transactions_url),
+        bitcoin_price_query
+    )?;
 
     // Here we serialize the bitcoin_price JSON
     // to get bitcoin_price
-    let parsed: Value = serde_json::from_str(&bitcoin_price_json)?;
-    let bitcoin_price = parsed["market_price_usd"].as_f64().unwrap();
-    let timestamp = chrono::offset::Local::now().time();
+    let quote = match bitcoin_price_json {
+        Some(bitcoin_price_json) => PriceQuote::parse(&bitcoin_price_json, currency, format)?,
+        None => None,
+    };
 
-    // Parse the JSON string into a `Value`
-    let parsed: Value = serde_json::from_str(&undefined_transaction_json)?;
+    let undefined_transactions = parse_unconfirmed_transactions(
+        &undefined_transaction_json,
+        quote.map(|quote| quote.price),
+        number_outputs,
+        exclude_change,
+    )?;
 
-    // Extract transactions array
-    let empty_vec: Vec<Value> = Vec::new();
-    let transactions = parsed["txs"].as_array().unwrap_or(&empty_vec);
+    Ok((undefined_transactions, quote))
+}
 
-    // Limit number of transactions
-    let transactions = transactions.iter().take(number_outputs);
+/// Builds the rows of the unconfirmed table from the feed JSON, the fiat amounts
+/// are left out when there is no `bitcoin_price` and with `exclude_change` the
+/// amount is the net flow, without the change
+fn parse_unconfirmed_transactions(
+    undefined_transaction_json: &str,
+    bitcoin_price: Option<f64>,
+    number_outputs: usize,
+    exclude_change: bool,
+) -> Result<Vec<UndefinedTransaction>, Box<dyn std::error::Error>> {
+    let now = Local::now();
 
-    let undefined_transaction_vec: Vec<UndefinedTransaction> = transactions
-        .map(|tx| {
-            let hash = tx["hash"].as_str().unwrap_or("").to_string();
+    let feed: UnconfirmedFeed = serde_json::from_str(undefined_transaction_json)?;
 
-            // Sum all `value` fields in the `out` array
-            let amount_satoshis: i64 = tx["out"]
-                .as_array()
-                .unwrap_or(&vec![])
+    let undefined_transaction_vec: Vec<UndefinedTransaction> = feed
+        .txs
+        .into_iter()
+        .take(number_outputs)
+        .map(|tx| {
+            // Outputs paying back to one of the input addresses are change, not a transfer
+            let input_addresses: Vec<&str> = tx
+                .inputs
                 .iter()
-                .filter_map(|out| out["value"].as_i64())
+                .filter_map(|input| input.prev_out.as_ref()?.addr.as_deref())
+                .collect();
+            let net_flow_satoshis: u64 = tx
+                .out
+                .iter()
+                .filter(|out| {
+                    out.addr
+                        .as_deref()
+                        .is_none_or(|addr| !input_addresses.contains(&addr))
+                })
+                .map(|out| out.value)
                 .sum();
 
-            // Convert to Bitcoin and calculate USD value
+            let amount_satoshis: u64 = if exclude_change {
+                net_flow_satoshis
+            } else {
+                tx.out.iter().map(|out| out.value).sum()
+            };
+
+            // Convert to Bitcoin and calculate the fiat value
             let amount_bitcoin = amount_satoshis as f64 / SATOSHIS_PER_BTC as f64;
-            let amount_usd = amount_bitcoin * bitcoin_price;
+            let amount_fiat = bitcoin_price.map(|bitcoin_price| amount_bitcoin * bitcoin_price);
 
-            UndefinedTransaction::new(hash, amount_bitcoin, amount_usd, timestamp)
+            // Arrival time in the mempool, the time of the request if the feed lacks it
+            let time = tx
+                .time
+                .and_then(|time| DateTime::from_timestamp(time, 0))
+                .map(|time| time.with_timezone(&Local))
+                .unwrap_or(now);
+
+            let fee_rate = tx.fee.and_then(|fee| fees::fee_rate(fee, tx.vsize()));
+
+            UndefinedTransaction {
+                hash: tx.hash,
+                amount_satoshis,
+                amount_fiat,
+                time,
+                relayed_by: tx.relayed_by.unwrap_or_else(|| "unknown".to_string()),
+                double_spend: tx.double_spend,
+                fee_rate,
+                net_flow_satoshis,
+                sender: primary_sender(&tx.inputs),
+            }
         })
         .collect();
 
     Ok(undefined_transaction_vec)
 }
 
+/// Unconfirmed transactions that arrived in the same interval of time
+#[derive(Debug, Serialize)]
+struct TimeBucket {
+    start: DateTime<Local>,
+    end: DateTime<Local>,
+    transactions: usize,
+    amount_satoshis: u64,
+}
+
+/// Groups the transactions in intervals of `minutes` by arrival time, oldest first
+fn group_by_time(undefined_transactions: &[UndefinedTransaction], minutes: u64) -> Vec<TimeBucket> {
+    let interval = minutes as i64 * 60;
+
+    let mut buckets: BTreeMap<i64, (usize, u64)> = BTreeMap::new();
+    for undefined_transaction in undefined_transactions {
+        let timestamp = undefined_transaction.time.timestamp();
+        let bucket = buckets
+            .entry(timestamp - timestamp.rem_euclid(interval))
+            .or_default();
+        bucket.0 += 1;
+        bucket.1 += undefined_transaction.amount_satoshis;
+    }
+
+    buckets
+        .into_iter()
+        .filter_map(|(start, (transactions, amount_satoshis))| {
+            Some(TimeBucket {
+                start: DateTime::from_timestamp(start, 0)?.with_timezone(&Local),
+                end: DateTime::from_timestamp(start + interval, 0)?.with_timezone(&Local),
+                transactions,
+                amount_satoshis,
+            })
+        })
+        .collect()
+}
+
+/// The `n` transactions paying the highest fee rate, the ones without a known
+/// rate go last and the ties are ordered by arrival time and hash
+fn top_by_fee_rate(
+    mut undefined_transactions: Vec<UndefinedTransaction>,
+    n: usize,
+) -> Vec<UndefinedTransaction> {
+    undefined_transactions.sort_by(|a, b| {
+        let ordering = match (a.fee_rate, b.fee_rate) {
+            (Some(rate_a), Some(rate_b)) => rate_b.total_cmp(&rate_a),
+            (Some(_), None) => std::cmp::Ordering::Less,
+            (None, Some(_)) => std::cmp::Ordering::Greater,
+            (None, None) => std::cmp::Ordering::Equal,
+        };
+
+        ordering
+            .then_with(|| a.time.cmp(&b.time))
+            .then_with(|| a.hash.cmp(&b.hash))
+    });
+    undefined_transactions.truncate(n);
+
+    undefined_transactions
+}
+
+/// Number of transactions waiting in the mempool, the endpoint answers a plain integer
+async fn unconfirmed_count(
+    http: &HttpClient,
+    unconfirmed_count_url: &str,
+) -> Result<u64, Box<dyn std::error::Error>> {
+    let count = http.fetch_text(unconfirmed_count_url).await?;
+
+    Ok(count.trim().parse()?)
+}
+
+/// The value --show prints for a transaction, a bare number so scripts can use it
+/// as is, or N/A when the transaction has none (i.e the fee of a coinbase)
+fn shown_value(
+    cli: &Cli,
+    transaction: &Transaction,
+    shown: ShownValue,
+    chain_tip: Option<u64>,
+) -> String {
+    let amount = |satoshis: u64| {
+        if cli.satoshis {
+            satoshis.to_string()
+        } else {
+            btc_amount(satoshis).normalize().to_string()
+        }
+    };
+
+    match shown {
+        ShownValue::Fee => transaction
+            .fee()
+            .filter(|_| !transaction.is_coinbase())
+            .map(amount)
+            .unwrap_or_else(|| "N/A".to_string()),
+        ShownValue::TotalInput => amount(transaction.total_input()),
+        ShownValue::TotalOutput => amount(transaction.total_output()),
+        // The block that confirmed it counts as the first confirmation
+        ShownValue::Confirmations => match (transaction.block_height, chain_tip) {
+            (Some(block_height), Some(chain_tip)) => {
+                (chain_tip.saturating_sub(block_height) + 1).to_string()
+            }
+            _ => "0".to_string(),
+        },
+    }
+}
+
+/// Height of the chain tip, the endpoint answers a plain integer
+async fn block_count(
+    http: &HttpClient,
+    block_count_url: &str,
+) -> Result<u64, Box<dyn std::error::Error>> {
+    let count = http.fetch_text(block_count_url).await?;
+
+    Ok(count.trim().parse()?)
+}
+
 async fn inspect_transaction(
     transaction_hash: &str,
     inspect_transaction_url: &str,
-) -> Result<Transaction, Box<dyn std::error::Error>> {
-    let inspect_transaction_url = format!("{inspect_transaction_url}{transaction_hash}");
+    http: &HttpClient,
+) -> Result<(Transaction, String), Box<dyn std::error::Error>> {
+    let inspect_transaction_json = http
+        .fetch_text(&format!("{inspect_transaction_url}{transaction_hash}"))
+        .await?;
 
-    let inspect_transaction_query = async {
-        let response = reqwest::get(&inspect_transaction_url).await?;
+    // Serialize the JSON, the raw response is kept for --round-trip-verify
+    let transaction: Transaction = serde_json::from_str(&inspect_transaction_json)?;
 
-        if !response.status().is_success() {
-            return Err(Box::<dyn std::error::Error>::from(
-                "There is no transaction with the provided hash",
-            ));
-        }
-
-        response.text().await.map_err(|e| e.into())
-    };
+    Ok((transaction, inspect_transaction_json))
+}
 
-    let inspect_transaction_json = tokio::join!(inspect_transaction_query);
+/// Fetches the transactions of a batch `concurrency` at a time, each distinct hash
+/// once, with a progress bar
+async fn fetch_transactions(
+    hashes: &[String],
+    inspect_transaction_url: &str,
+    http: &HttpClient,
+    concurrency: NonZeroUsize,
+    quiet: bool,
+) -> HashMap<String, Result<(Transaction, String), Box<dyn std::error::Error>>> {
+    let mut distinct: Vec<&String> = hashes.iter().collect();
+    distinct.sort();
+    distinct.dedup();
 
-    // Serialize the JSON
-    let transaction: Transaction = serde_json::from_str(&inspect_transaction_json.0?)?;
+    let progress = batch_progress(distinct.len(), quiet);
+    let fetched = futures::stream::iter(distinct)
+        .map(|hash| async {
+            let fetched = inspect_transaction(hash, inspect_transaction_url, http).await;
+            progress.inc(1);
+            (hash.clone(), fetched)
+        })
+        .buffer_unordered(concurrency.get())
+        .collect()
+        .await;
+    progress.finish_and_clear();
 
-    Ok(transaction)
+    fetched
 }
 
 async fn inspect_address(
     bitcoin_address: &str,
     inspect_address_url: &str,
+    http: &HttpClient,
 ) -> Result<BitcoinAddress, Box<dyn std::error::Error>> {
-    let inspect_address_url = format!("{inspect_address_url}{bitcoin_address}");
+    let inspect_address_json = http
+        .fetch_text(&format!("{inspect_address_url}{bitcoin_address}"))
+        .await?;
+
+    // Serialize the JSON
+    let bitcoin_address: BitcoinAddress = serde_json::from_str(&inspect_address_json)?;
+
+    Ok(bitcoin_address)
+}
+
+/// Why a transaction couldn't be fetched, only a not found means the hash is unknown,
+/// a timeout or an API error doesn't tell anything about it
+fn transaction_error_message(hash: &str, error: &(dyn std::error::Error + 'static)) -> String {
+    match error.downcast_ref::<FetchError>() {
+        Some(FetchError::Status(reqwest::StatusCode::NOT_FOUND)) => {
+            format!("[!] There is not transaction with the hash {hash}")
+        }
+        _ => format!("[!] Couldn't fetch {hash}: {error}"),
+    }
+}
 
-    let inspect_address_query = async {
-        let response = reqwest::get(&inspect_address_url).await?;
+/// Why an address couldn't be fetched, older explorers answer 400 to the Taproot
+/// addresses they don't support rather than a not found
+fn address_error_message(address: &str, error: &(dyn std::error::Error + 'static)) -> String {
+    let rejected = matches!(
+        error.downcast_ref::<FetchError>(),
+        Some(FetchError::Status(reqwest::StatusCode::BAD_REQUEST))
+    );
 
-        if !response.status().is_success() {
-            return Err(Box::<dyn std::error::Error>::from(
-                "There is no transaction with the provided hash",
-            ));
+    if rejected && classify_address_type(address) == AddressType::Taproot {
+        format!("[!] Couldn't fetch the address {address}: this explorer may not support Taproot addresses; try --api-base with a newer explorer")
+    } else {
+        format!("[!] Couldn't fetch the address {address}: {error}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use output::SymbolPosition;
+
+    fn transaction(inputs: &[u64], outputs: &[u64]) -> Transaction {
+        Transaction {
+            inputs: inputs
+                .iter()
+                .map(|&value| Input {
+                    prev_out: Some(PrevOut {
+                        addr: Some("bc1qinput".to_string()),
+                        value,
+                        hash: None,
+                        tx_index: None,
+                        n: None,
+                    }),
+                })
+                .collect(),
+            out: outputs
+                .iter()
+                .map(|&value| Output {
+                    value,
+                    addr: Some("bc1qoutput".to_string()),
+                })
+                .collect(),
+            hash: "0".repeat(64),
+            size: 0,
+            weight: 0,
+            block_height: None,
         }
+    }
 
-        response.text().await.map_err(|e| e.into())
-    };
+    #[test]
+    fn warns_on_abnormal_input_and_output_counts() {
+        let transaction = transaction(&[1_000; 3], &[500]);
 
-    let inspect_addres_json = tokio::join!(inspect_address_query);
+        assert!(size_warnings(&transaction, 3, 3).is_empty());
+        assert!(size_warnings(&transaction, 0, 0).is_empty());
 
-    // Serialize the JSON
-    let bitcoin_address: BitcoinAddress = serde_json::from_str(&inspect_addres_json.0?)?;
+        let warnings = size_warnings(&transaction, 2, 0);
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("3 inputs"));
+    }
 
-    Ok(bitcoin_address)
+    #[test]
+    fn rejects_invalid_time_formats() {
+        assert_eq!(parse_time_format("%I:%M %p").unwrap(), "%I:%M %p");
+        assert!(parse_time_format("%H:%Q").is_err());
+    }
+
+    #[test]
+    fn locale_picks_the_separators_and_the_symbol_side() {
+        let options = OutputOptions::from_cli(&Cli::parse_from([
+            "btcAnalyser",
+            "--currency",
+            "EUR",
+            "--locale",
+            "de",
+        ]));
+        assert_eq!(options.thousands_separator, ".");
+        assert_eq!(options.decimal_separator, ",");
+        assert_eq!(options.symbol_position, SymbolPosition::After);
+
+        let options = OutputOptions::from_cli(&Cli::parse_from([
+            "btcAnalyser",
+            "--locale",
+            "nl",
+            "--thousands-sep",
+            " ",
+        ]));
+        assert_eq!(options.thousands_separator, " ");
+        assert_eq!(options.decimal_separator, ",");
+        assert_eq!(options.symbol_position, SymbolPosition::Before);
+    }
+
+    #[test]
+    fn precise_time_adds_the_seconds() {
+        let time_format_of = |args: &[&str]| {
+            let cli = Cli::parse_from([&["btcAnalyser"], args].concat());
+            time_format(&cli).to_string()
+        };
+
+        assert_eq!(time_format_of(&[]), "%H:%M");
+        assert_eq!(time_format_of(&["--precise-time"]), "%H:%M:%S");
+        assert_eq!(time_format_of(&["--seconds", "--12h"]), "%I:%M:%S %p");
+    }
+
+    #[test]
+    fn fee_rate_is_per_vbyte_and_per_weight_unit() {
+        let mut paying = transaction(&[100_000], &[95_000]);
+        paying.weight = 561;
+        assert_eq!(fee_rate_label(&paying), "35.5 sat/vB (8.9 sat/WU)");
+
+        // Without the weight the size counts 4 weight units per byte
+        paying.weight = 0;
+        paying.size = 250;
+        assert_eq!(fee_rate_label(&paying), "20.0 sat/vB (5.0 sat/WU)");
+
+        assert_eq!(fee_rate_label(&transaction(&[1_000], &[1_000])), "0 sat/vB");
+        assert_eq!(fee_rate_label(&transaction(&[1_000], &[900])), "N/A");
+    }
+
+    #[test]
+    fn fee_is_inputs_minus_outputs() {
+        let transaction = transaction(&[60_000, 50_000], &[90_000, 15_000]);
+        assert_eq!(transaction.fee(), Some(5_000));
+    }
+
+    #[test]
+    fn fee_is_none_when_outputs_exceed_inputs() {
+        let transaction = transaction(&[10_000], &[8_000, 4_000]);
+        assert_eq!(transaction.total_output(), 12_000);
+        assert_eq!(transaction.fee(), None);
+    }
+
+    #[test]
+    fn spends_from_names_the_funding_output() {
+        let prev_out = |json: &str| serde_json::from_str::<PrevOut>(json).unwrap();
+        let hash = "136937e5a742645ce873f079f8668aefdc2d06b8172e903d031a8bfb48969450";
+
+        assert_eq!(
+            prev_out(&format!(r#"{{"value": 1, "txid": "{hash}", "n": 1}}"#)).spends_from(true),
+            Some("136937e5…48969450:1".to_string())
+        );
+        assert_eq!(
+            prev_out(r#"{"value": 1, "tx_index": 8182746, "n": 0}"#).spends_from(false),
+            Some("tx_index 8182746:0".to_string())
+        );
+        assert_eq!(prev_out(r#"{"value": 1}"#).spends_from(false), None);
+    }
+
+    #[test]
+    fn primary_sender_spends_the_most() {
+        let mut transaction = transaction(&[10_000, 50_000], &[55_000]);
+        transaction.inputs[1].prev_out.as_mut().unwrap().addr = Some("bc1qbig".to_string());
+
+        assert_eq!(
+            primary_sender(&transaction.inputs),
+            Some("bc1qbig".to_string())
+        );
+        assert_eq!(primary_sender(&[]), None);
+    }
+
+    #[test]
+    fn recipients_leave_the_change_out() {
+        let mut payment = transaction(&[100_000], &[60_000, 30_000]);
+        payment.out[1].addr = Some("bc1qinput".to_string());
+
+        let recipients = payment.recipients().unwrap();
+        assert_eq!(recipients.len(), 1);
+        assert_eq!(recipients[0].value, 60_000);
+
+        // Nothing goes back to the inputs, or everything does
+        assert!(transaction(&[100_000], &[60_000, 30_000])
+            .recipients()
+            .is_none());
+        payment.out[0].addr = Some("bc1qinput".to_string());
+        assert!(payment.recipients().is_none());
+    }
+
+    #[test]
+    fn confirmation_eta_counts_blocks_and_minutes() {
+        assert_eq!(confirmation_eta(Some(1)), "~1 block (~10 min)");
+        assert_eq!(confirmation_eta(Some(2)), "~2 blocks (~20 min)");
+        assert_eq!(confirmation_eta(Some(9)), "~9 blocks (~1h 30min)");
+        assert!(confirmation_eta(None).starts_with("unlikely"));
+    }
+
+    #[test]
+    fn involvement_tells_the_role_of_the_address() {
+        let cli = Cli::parse_from(["btcAnalyser", "--no-color"]);
+        let mut payment = transaction(&[100_000], &[60_000, 30_000]);
+        payment.out[0].addr = Some("bc1qreceiver".to_string());
+        payment.out[1].addr = Some("bc1qinput".to_string());
+
+        assert_eq!(
+            involvement(&cli, &payment, "bc1qreceiver"),
+            "bc1qreceiver is a recipient, it received 0.0006 BTC"
+        );
+        assert_eq!(
+            involvement(&cli, &payment, "bc1qinput"),
+            "bc1qinput is both a sender and a recipient, it spent 0.001 BTC and received 0.0003 BTC"
+        );
+        assert_eq!(
+            involvement(&cli, &payment, "bc1qstranger"),
+            "bc1qstranger isn't involved in this transaction"
+        );
+    }
+
+    #[test]
+    fn outputs_fall_in_their_value_bucket() {
+        let transaction = transaction(&[], &[50_000, 100_000, 999_999, 10_000_000, 20_000_000]);
+        let outputs: Vec<&Output> = transaction.out.iter().collect();
+
+        let buckets: Vec<(usize, u64)> = value_buckets(&outputs)
+            .iter()
+            .map(|bucket| (bucket.count, bucket.total))
+            .collect();
+        assert_eq!(
+            buckets,
+            [(1, 50_000), (2, 1_099_999), (0, 0), (2, 30_000_000)]
+        );
+    }
+
+    #[test]
+    fn outputs_over_the_maximum_are_large() {
+        let transaction = transaction(&[300_000_000], &[200_000_000, 100_000_000]);
+
+        assert!(is_large_output(&transaction.out[0], Some(1.5)));
+        assert!(!is_large_output(&transaction.out[1], Some(1.0)));
+        assert!(!is_large_output(&transaction.out[0], None));
+    }
+
+    #[test]
+    fn invalid_prices_are_discarded() {
+        assert_eq!(
+            validate_price(65_000.5, "USD", OutputFormat::Table),
+            Some(65_000.5)
+        );
+        assert_eq!(validate_price(0.0, "USD", OutputFormat::Table), None);
+        assert_eq!(validate_price(-1.0, "USD", OutputFormat::Table), None);
+        assert_eq!(validate_price(f64::NAN, "USD", OutputFormat::Table), None);
+        assert_eq!(
+            validate_price(f64::INFINITY, "USD", OutputFormat::Table),
+            None
+        );
+    }
+
+    #[test]
+    fn price_is_read_from_the_stats_or_the_ticker_layout() {
+        let stats = r#"{"market_price_usd": 65000.5}"#;
+        let ticker = r#"{"USD": {"15m": 64000.0, "last": 65000.5}, "EUR": {"15m": 60000.0}}"#;
+
+        assert_eq!(parse_bitcoin_price(stats, "USD").unwrap(), 65_000.5);
+        assert_eq!(parse_bitcoin_price(ticker, "USD").unwrap(), 65_000.5);
+        assert_eq!(parse_bitcoin_price(ticker, "EUR").unwrap(), 60_000.0);
+        assert!(parse_bitcoin_price(stats, "EUR").is_err());
+    }
+
+    #[test]
+    fn price_age_comes_from_the_stats_timestamp() {
+        let stats = r#"{"market_price_usd": 65000.5, "timestamp": 1700000000000.0}"#;
+        let quote = PriceQuote::parse(stats, "USD", OutputFormat::Table)
+            .unwrap()
+            .unwrap();
+        assert_eq!(quote.timestamp.unwrap().timestamp(), 1_700_000_000);
+
+        let ticker = r#"{"USD": {"last": 65000.5}}"#;
+        assert!(PriceQuote::parse(ticker, "USD", OutputFormat::Table)
+            .unwrap()
+            .unwrap()
+            .timestamp
+            .is_none());
+
+        assert_eq!(format_age(chrono::Duration::seconds(45)), "45s");
+        assert_eq!(format_age(chrono::Duration::seconds(750)), "12 min");
+        assert_eq!(format_age(chrono::Duration::seconds(11_100)), "3h 05min");
+    }
+
+    #[test]
+    fn zero_price_falls_back_to_btc_only() {
+        let feed = r#"{"txs": [{"hash": "abc", "out": [{"value": 150000}, {"value": 50000}]}]}"#;
+
+        let undefined_transactions = parse_unconfirmed_transactions(
+            feed,
+            validate_price(0.0, "USD", OutputFormat::Table),
+            10,
+            false,
+        )
+        .unwrap();
+
+        assert_eq!(undefined_transactions[0].amount_satoshis, 200_000);
+        assert_eq!(undefined_transactions[0].amount_fiat, None);
+    }
+
+    #[test]
+    fn net_flow_leaves_out_the_change() {
+        let feed = r#"{"txs": [{
+            "hash": "abc",
+            "inputs": [{"prev_out": {"addr": "bc1qsender", "value": 100000}}],
+            "out": [
+                {"addr": "bc1qreceiver", "value": 30000},
+                {"addr": "bc1qsender", "value": 69000}
+            ]
+        }]}"#;
+
+        let undefined_transactions = parse_unconfirmed_transactions(feed, None, 10, false).unwrap();
+
+        assert_eq!(undefined_transactions[0].amount_satoshis, 99_000);
+        assert_eq!(undefined_transactions[0].net_flow_satoshis, 30_000);
+
+        let undefined_transactions =
+            parse_unconfirmed_transactions(feed, Some(50_000.0), 10, true).unwrap();
+
+        assert_eq!(undefined_transactions[0].amount_satoshis, 30_000);
+        assert!((undefined_transactions[0].amount_fiat.unwrap() - 15.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn groups_by_arrival_time() {
+        let feed = r#"{"txs": [
+            {"hash": "a", "time": 1700000110, "out": [{"value": 1000}]},
+            {"hash": "b", "time": 1700000390, "out": [{"value": 2000}]},
+            {"hash": "c", "time": 1700000410, "out": [{"value": 4000}]}
+        ]}"#;
+
+        let undefined_transactions = parse_unconfirmed_transactions(feed, None, 10, false).unwrap();
+        let time_buckets = group_by_time(&undefined_transactions, 5);
+
+        // 1700000110 and 1700000390 fall in the 5 minutes starting at 1700000100
+        assert_eq!(time_buckets.len(), 2);
+        assert_eq!(time_buckets[0].start.timestamp(), 1_700_000_100);
+        assert_eq!(time_buckets[0].transactions, 2);
+        assert_eq!(time_buckets[0].amount_satoshis, 3000);
+        assert_eq!(time_buckets[1].transactions, 1);
+    }
+
+    #[test]
+    fn top_fee_ranks_by_fee_rate() {
+        let feed = r#"{"txs": [
+            {"hash": "low", "fee": 1000, "weight": 800, "out": []},
+            {"hash": "unknown", "out": []},
+            {"hash": "high", "fee": 5000, "size": 250, "out": []}
+        ]}"#;
+
+        let undefined_transactions = parse_unconfirmed_transactions(feed, None, 10, false).unwrap();
+        let top = top_by_fee_rate(undefined_transactions, 2);
+
+        assert_eq!(top.len(), 2);
+        assert_eq!(top[0].hash, "high");
+        assert_eq!(top[0].fee_rate, Some(20.0));
+        assert_eq!(top[1].fee_rate, Some(5.0));
+    }
+
+    #[test]
+    fn sort_breaks_ties_by_time_and_hash() {
+        let feed = r#"{"txs": [
+            {"hash": "b", "time": 1700000000, "out": [{"value": 1000}]},
+            {"hash": "big", "time": 1700000100, "out": [{"value": 5000}]},
+            {"hash": "a", "time": 1700000000, "out": [{"value": 1000}]},
+            {"hash": "c", "time": 1699999000, "out": [{"value": 1000}]}
+        ]}"#;
+
+        let mut undefined_transactions =
+            parse_unconfirmed_transactions(feed, None, 10, false).unwrap();
+        sort_unconfirmed(&mut undefined_transactions, SortKey::Amount, true);
+
+        let hashes: Vec<&str> = undefined_transactions
+            .iter()
+            .map(|undefined_transaction| undefined_transaction.hash.as_str())
+            .collect();
+        assert_eq!(hashes, ["big", "c", "a", "b"]);
+    }
+
+    #[test]
+    fn simple_payment_is_one_sender_and_one_recipient() {
+        let mut payment = transaction(&[60_000, 50_000], &[100_000, 9_000]);
+        payment.out[1].addr = Some("bc1qinput".to_string());
+
+        let simple = simple_payment(&payment).unwrap();
+        assert_eq!(simple.from, "bc1qinput");
+        assert_eq!(simple.to, "bc1qoutput");
+        assert_eq!(simple.amount, 100_000);
+        assert_eq!(simple.fee, 1_000);
+
+        // A third output makes it a batch
+        assert!(simple_payment(&transaction(&[60_000], &[10_000, 10_000, 10_000])).is_none());
+
+        // Two senders
+        payment.inputs[1].prev_out.as_mut().unwrap().addr = Some("bc1qother".to_string());
+        assert!(simple_payment(&payment).is_none());
+    }
+
+    #[test]
+    fn coinbase_inputs_have_no_previous_output() {
+        let transaction: Transaction = serde_json::from_str(
+            r#"{"hash": "abc", "inputs": [{"sequence": 0}], "out": [{"value": 312500000}]}"#,
+        )
+        .unwrap();
+
+        assert!(transaction.is_coinbase());
+        assert_eq!(transaction.total_input(), 0);
+        assert_eq!(transaction.out[0].addr, None);
+    }
+
+    #[test]
+    fn preview_keeps_an_on_chain_prefix() {
+        assert_eq!(preview_len(10, Some(3)), 3);
+        assert_eq!(preview_len(2, Some(3)), 2);
+        assert_eq!(preview_len(10, None), 10);
+    }
+
+    #[test]
+    fn shown_values_are_bare_numbers() {
+        let cli = Cli::parse_from(["btcAnalyser", "--show", "fee"]);
+        let mut transaction = transaction(&[100_000], &[90_000]);
+
+        assert_eq!(
+            shown_value(&cli, &transaction, ShownValue::Fee, None),
+            "0.0001"
+        );
+        assert_eq!(
+            shown_value(&cli, &transaction, ShownValue::TotalOutput, None),
+            "0.0009"
+        );
+        assert_eq!(
+            shown_value(&cli, &transaction, ShownValue::Confirmations, Some(820_005)),
+            "0"
+        );
+
+        transaction.block_height = Some(820_000);
+        assert_eq!(
+            shown_value(&cli, &transaction, ShownValue::Confirmations, Some(820_005)),
+            "6"
+        );
+
+        let cli = Cli::parse_from(["btcAnalyser", "--show", "fee", "--satoshis"]);
+        assert_eq!(
+            shown_value(&cli, &transaction, ShownValue::Fee, None),
+            "10000"
+        );
+    }
+
+    #[test]
+    fn fiat_decimals_round_the_fiat_amounts() {
+        let quote = PriceQuote {
+            price: 65_000.6,
+            timestamp: None,
+        };
+
+        let cli = Cli::parse_from(["btcAnalyser", "--fiat-decimals", "0"]);
+        assert_eq!(price_label(&cli, &quote, "USD"), "$65,001");
+
+        let cli = Cli::parse_from(["btcAnalyser", "--fiat-decimals", "4"]);
+        assert_eq!(price_label(&cli, &quote, "USD"), "$65,000.60");
+    }
+
+    #[test]
+    fn double_spends_are_red_with_an_alert() {
+        let undefined_transactions = parse_unconfirmed_transactions(
+            include_str!("../tests/fixtures/unconfirmed.json"),
+            None,
+            100,
+            false,
+        )
+        .unwrap();
+        let cli = Cli::parse_from(["btcAnalyser"]);
+        let conflicting = &undefined_transactions[1];
+        assert!(conflicting.double_spend);
+
+        let alert = Color::Red
+            .paint(format!("{} ⚠ double-spend", conflicting.hash))
+            .to_string();
+        let honest = Color::Yellow
+            .paint(&undefined_transactions[0].hash)
+            .to_string();
+
+        let new: Vec<&UndefinedTransaction> = undefined_transactions.iter().collect();
+        for table in [
+            top_fee_table(&cli, &undefined_transactions),
+            watch::new_transactions_table(&cli, &new),
+        ] {
+            let shown = table.display().unwrap().to_string();
+            assert!(shown.contains(&alert), "{shown}");
+            assert!(shown.contains(&honest), "{shown}");
+        }
+    }
 }
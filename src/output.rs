@@ -0,0 +1,299 @@
+use std::fmt::Display;
+use std::io::IsTerminal;
+use std::sync::OnceLock;
+
+use ansi_term::{Color, Style};
+use clap::ValueEnum;
+use cli_table::format::{Border, Justify, Separator};
+use cli_table::{Cell, CellStruct, ColorChoice, TableStruct};
+use num_format::{CustomFormat, Locale};
+
+use crate::Cli;
+
+/// Every display decision that depends on the command line flags, set once at
+/// startup so the tables and messages don't each check the flags on their own
+#[derive(Debug, Clone)]
+pub struct OutputOptions {
+    pub color: bool,
+    pub borders: bool,
+    pub thousands_separator: String,
+    pub decimal_separator: String,
+    // Side of the fiat amounts the currency symbol goes, from --locale
+    pub symbol_position: SymbolPosition,
+    // The BTC amounts are only grouped when --thousands-sep asks for it
+    pub btc_thousands_separator: String,
+    pub amount_align: Alignment,
+    // Width the amounts are padded to, 0 leaves them to the table
+    pub amount_width: usize,
+    // Chain of the explorer pages the hashes and addresses link to, with --links
+    pub links: Option<&'static str>,
+    pub summary_color: SummaryColor,
+}
+
+/// Colors of the total rows under the tables
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum SummaryColor {
+    /// Purple in a magenta table, apart from the rest
+    Purple,
+    /// The color of the table they sum up
+    Body,
+    /// No color
+    None,
+}
+
+/// Side of a fiat amount its currency symbol is written on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SymbolPosition {
+    /// `€1,234.56`
+    Before,
+    /// `1.234,56 €`
+    After,
+}
+
+/// Languages that write the currency symbol after the amount
+const SYMBOL_AFTER_LANGUAGES: &[&str] = &[
+    "be", "bg", "ca", "cs", "da", "de", "el", "es", "et", "fi", "fr", "hr", "hu", "is", "it", "lt",
+    "lv", "nb", "nn", "pl", "ro", "ru", "sk", "sl", "sr", "sv", "uk", "vi",
+];
+
+/// Where a locale writes the currency symbol. It follows the language, apart from
+/// the regions that write it the other way: Switzerland puts it first in every
+/// language, the Spanish of the Americas puts it first and the Portuguese of
+/// Portugal puts it last (CLDR's plain `pt` is Brazil, that puts it first)
+pub fn symbol_position(locale: &Locale) -> SymbolPosition {
+    let name = locale.name();
+    let (language, region) = name.split_once('-').unwrap_or((name, ""));
+
+    let after = match (language, region) {
+        (_, "CH") => false,
+        ("pt", region) => region == "PT",
+        ("es", region) => region.is_empty() || region == "ES",
+        (language, _) => SYMBOL_AFTER_LANGUAGES.contains(&language),
+    };
+
+    if after {
+        SymbolPosition::After
+    } else {
+        SymbolPosition::Before
+    }
+}
+
+/// Side of their column the amounts are aligned to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Alignment {
+    Left,
+    Right,
+}
+
+impl OutputOptions {
+    /// --plain implies --no-color, and drops the table borders and thousands
+    /// separators so the output is easy to handle with awk/grep. --thousands-sep
+    /// and --decimal-sep override the separators of --locale, English by default
+    pub fn from_cli(cli: &Cli) -> OutputOptions {
+        let locale = cli.locale.unwrap_or(Locale::en);
+        let default_separator = if cli.plain { "" } else { locale.separator() };
+
+        OutputOptions {
+            color: !cli.no_color && !cli.plain,
+            borders: !cli.plain,
+            thousands_separator: cli
+                .thousands_sep
+                .clone()
+                .unwrap_or_else(|| default_separator.to_string()),
+            decimal_separator: cli
+                .decimal_sep
+                .clone()
+                .unwrap_or_else(|| locale.decimal().to_string()),
+            symbol_position: symbol_position(&locale),
+            btc_thousands_separator: cli.thousands_sep.clone().unwrap_or_default(),
+            amount_align: cli.amount_align,
+            amount_width: cli.amount_width.unwrap_or(0),
+            // Escape sequences only make sense to a terminal that shows the colors
+            links: (cli.links && !cli.no_color && !cli.plain && std::io::stdout().is_terminal())
+                .then_some(if cli.testnet { "btc-testnet" } else { "btc" }),
+            summary_color: cli.summary_color,
+        }
+    }
+}
+
+impl Default for OutputOptions {
+    fn default() -> OutputOptions {
+        OutputOptions {
+            color: true,
+            borders: true,
+            thousands_separator: Locale::en.separator().to_string(),
+            decimal_separator: Locale::en.decimal().to_string(),
+            symbol_position: SymbolPosition::Before,
+            btc_thousands_separator: String::new(),
+            amount_align: Alignment::Right,
+            amount_width: 0,
+            links: None,
+            summary_color: SummaryColor::Purple,
+        }
+    }
+}
+
+static OUTPUT_OPTIONS: OnceLock<OutputOptions> = OnceLock::new();
+
+pub fn init(options: OutputOptions) {
+    let _ = OUTPUT_OPTIONS.set(options);
+}
+
+pub fn options() -> &'static OutputOptions {
+    OUTPUT_OPTIONS.get_or_init(OutputOptions::default)
+}
+
+/// Explorer pages a hash or an address can link to
+#[derive(Debug, Clone, Copy)]
+pub enum Linked {
+    Transaction,
+    Address,
+}
+
+/// Wraps `text` in an OSC 8 hyperlink to the explorer page of `id` with --links,
+/// the terminal still shows `text` and opens the page on a click
+pub fn link(linked: Linked, id: &str, text: String) -> String {
+    let Some(chain) = options().links else {
+        return text;
+    };
+
+    let page = match linked {
+        Linked::Transaction => "transactions",
+        Linked::Address => "addresses",
+    };
+
+    format!("\x1b]8;;{EXPLORER_URL}/{page}/{chain}/{id}\x1b\\{text}\x1b]8;;\x1b\\")
+}
+
+const EXPLORER_URL: &str = "https://www.blockchain.com/explorer";
+
+/// Style of the text of a total row, `body` being the color of the table it sums up
+pub fn summary_style(body: Color) -> Style {
+    match options().summary_color {
+        SummaryColor::Purple => Color::Purple.normal(),
+        SummaryColor::Body => body.normal(),
+        SummaryColor::None => Style::new(),
+    }
+}
+
+/// Color of the borders of a total table, `body` being the color of the table it sums up
+pub fn summary_border(body: Color) -> Option<cli_table::Color> {
+    match options().summary_color {
+        SummaryColor::Purple => Some(cli_table::Color::Magenta),
+        SummaryColor::Body => Some(table_color(body)),
+        SummaryColor::None => None,
+    }
+}
+
+/// The table color closest to a text color
+fn table_color(color: Color) -> cli_table::Color {
+    match color {
+        Color::Black => cli_table::Color::Black,
+        Color::Red => cli_table::Color::Red,
+        Color::Green => cli_table::Color::Green,
+        Color::Yellow => cli_table::Color::Yellow,
+        Color::Blue => cli_table::Color::Blue,
+        Color::Purple => cli_table::Color::Magenta,
+        Color::Cyan => cli_table::Color::Cyan,
+        Color::White => cli_table::Color::White,
+        Color::Fixed(code) => cli_table::Color::Ansi256(code),
+        Color::RGB(r, g, b) => cli_table::Color::Rgb(r, g, b),
+    }
+}
+
+/// Paints `text` with `style`, or leaves it as is when the colors are disabled
+pub fn paint(style: impl Into<Style>, text: impl Display) -> String {
+    if options().color {
+        style.into().paint(text.to_string()).to_string()
+    } else {
+        text.to_string()
+    }
+}
+
+/// Pads an amount to the --amount-width, on the side given by --amount-align
+pub fn align_amount(amount: impl Display) -> String {
+    let options = options();
+    let width = options.amount_width;
+
+    match options.amount_align {
+        Alignment::Left => format!("{amount:<width$}"),
+        Alignment::Right => format!("{amount:>width$}"),
+    }
+}
+
+/// Table cell of a BTC or fiat amount, aligned as the output options say so the
+/// amounts of a column line up on their last digit by default
+pub fn amount_cell(style: impl Into<Style>, amount: impl Display) -> CellStruct {
+    let justify = match options().amount_align {
+        Alignment::Left => Justify::Left,
+        Alignment::Right => Justify::Right,
+    };
+
+    paint(style, align_amount(amount)).cell().justify(justify)
+}
+
+/// Number format of the amounts, with the separators of the output options
+pub fn number_format() -> CustomFormat {
+    let options = options();
+
+    CustomFormat::builder()
+        .decimal(&options.decimal_separator)
+        .minus_sign(Locale::en.minus_sign())
+        .separator(&options.thousands_separator)
+        .build()
+        .unwrap_or_default()
+}
+
+/// Checks a --thousands-sep or --decimal-sep, the number format takes up to 8 bytes
+pub fn parse_separator(value: &str) -> Result<String, String> {
+    if value.len() > 8 {
+        return Err(format!("{value} is too long for a separator"));
+    }
+
+    Ok(value.to_string())
+}
+
+/// Checks a --locale, by its name in the CLDR (i.e "de", "fr-CH" or "pt-PT")
+pub fn parse_locale(value: &str) -> Result<Locale, String> {
+    Locale::from_name(value)
+        .map_err(|_| format!("{value} isn't a known locale, i.e \"de\", \"fr-CH\" or \"pt-PT\""))
+}
+
+/// Prints a table with the colors and borders of the output options
+pub fn print_table(table: TableStruct) -> std::io::Result<()> {
+    let options = options();
+
+    let mut table = table;
+    if !options.color {
+        table = table.color_choice(ColorChoice::Never);
+    }
+    if !options.borders {
+        table = table
+            .border(Border::builder().build())
+            .separator(Separator::builder().build());
+    }
+
+    cli_table::print_stdout(table)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn symbol_position_follows_the_language_and_the_region() {
+        let position = |name| symbol_position(&parse_locale(name).unwrap());
+
+        assert_eq!(position("de"), SymbolPosition::After);
+        assert_eq!(position("fr"), SymbolPosition::After);
+        assert_eq!(position("pt-PT"), SymbolPosition::After);
+        assert_eq!(position("es"), SymbolPosition::After);
+        // A decimal comma doesn't make it go after, nor a decimal point before
+        assert_eq!(position("nl"), SymbolPosition::Before);
+        assert_eq!(position("pt"), SymbolPosition::Before);
+        assert_eq!(position("es-MX"), SymbolPosition::Before);
+        assert_eq!(position("de-CH"), SymbolPosition::Before);
+        assert_eq!(position("en"), SymbolPosition::Before);
+        assert!(parse_locale("xx").is_err());
+    }
+}
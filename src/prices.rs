@@ -0,0 +1,158 @@
+use ansi_term::Color;
+use cli_table::{Cell, Style, Table};
+use serde::Serialize;
+
+use crate::format::format_fiat;
+use crate::http::HttpClient;
+use crate::output::{amount_cell, number_format, paint, print_table};
+use crate::{format_age, print_result, Cli, OutputFormat, PriceQuote};
+
+/// Endpoints of the explorer the bitcoin price can be read from
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum PriceSource {
+    /// `/stats`, only in USD but it tells when the price was computed
+    Stats,
+    /// `/ticker`, every currency
+    Ticker,
+}
+
+impl PriceSource {
+    const ALL: [PriceSource; 2] = [PriceSource::Stats, PriceSource::Ticker];
+
+    fn url(self, api_base: &str) -> String {
+        match self {
+            PriceSource::Stats => format!("{api_base}/stats?format=json"),
+            PriceSource::Ticker => format!("{api_base}/ticker"),
+        }
+    }
+}
+
+/// What a source answered, the error when it gave no usable price
+#[derive(Debug, Serialize)]
+struct SourcePrice {
+    source: PriceSource,
+    url: String,
+    price: Option<f64>,
+    age_seconds: Option<i64>,
+    error: Option<String>,
+}
+
+/// Gap between the highest and the lowest of the prices
+fn spread(prices: &[f64]) -> Option<(f64, f64)> {
+    let lowest = prices.iter().copied().reduce(f64::min)?;
+    let highest = prices.iter().copied().reduce(f64::max)?;
+
+    Some((highest - lowest, (highest - lowest) / lowest * 100.0))
+}
+
+/// Fetches the price from every source at once and prints them side by side with
+/// their spread, to pick the one to trust and spot an outlier
+pub async fn compare_price_sources(
+    cli: &Cli,
+    http: &HttpClient,
+    api_base: &str,
+    currency: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let prices = futures::future::join_all(PriceSource::ALL.map(|source| async move {
+        let url = source.url(api_base);
+        let quote = match http.fetch_text(&url).await {
+            Ok(json) => {
+                PriceQuote::parse(&json, currency, cli.format).map_err(|error| error.to_string())
+            }
+            Err(error) => Err(error.to_string()),
+        };
+
+        let (price, age_seconds, error) = match quote {
+            Ok(Some(quote)) => (
+                Some(quote.price),
+                quote.age().map(|age| age.num_seconds()),
+                None,
+            ),
+            Ok(None) => (None, None, Some("invalid price".to_string())),
+            Err(error) => (None, None, Some(error)),
+        };
+
+        SourcePrice {
+            source,
+            url,
+            price,
+            age_seconds,
+            error,
+        }
+    }))
+    .await;
+
+    let valid: Vec<f64> = prices.iter().filter_map(|source| source.price).collect();
+    let spread = spread(&valid);
+
+    if cli.format != OutputFormat::Table {
+        return print_result(
+            cli,
+            &serde_json::json!({
+                "currency": currency,
+                "sources": prices,
+                "spread": spread.map(|(spread, _)| spread),
+                "spread_percent": spread.map(|(_, percent)| percent),
+            }),
+        );
+    }
+
+    let fiat =
+        |amount: f64| format_fiat(amount, currency, &number_format(), cli.output_precision_usd);
+
+    let table: Vec<_> = prices
+        .iter()
+        .map(|source| {
+            vec![
+                paint(Color::Cyan, format!("{:?}", source.source)).cell(),
+                paint(Color::Cyan, &source.url).cell(),
+                match (source.price, &source.error) {
+                    (Some(price), _) => amount_cell(Color::Cyan, fiat(price)),
+                    (None, error) => paint(Color::Red, error.as_deref().unwrap_or("N/A")).cell(),
+                },
+                paint(
+                    Color::Cyan,
+                    source
+                        .age_seconds
+                        .map(|age| format_age(chrono::Duration::seconds(age)))
+                        .unwrap_or_else(|| "N/A".to_string()),
+                )
+                .cell(),
+            ]
+        })
+        .collect();
+
+    print_table(
+        table
+            .table()
+            .title(vec![
+                "Source".cell().bold(true),
+                "URL".cell().bold(true),
+                format!("Price ({currency})").cell().bold(true),
+                "Age".cell().bold(true),
+            ])
+            .foreground_color(Some(cli_table::Color::Cyan)),
+    )?;
+    println!();
+
+    let spread = match spread {
+        Some((spread, percent)) if valid.len() > 1 => format!("{} ({percent:.2}%)", fiat(spread)),
+        _ => "N/A, fewer than two sources gave a price".to_string(),
+    };
+    println!("{} {spread}", paint(Color::Purple, "Spread:"));
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spread_is_relative_to_the_lowest_price() {
+        assert_eq!(spread(&[50_000.0, 50_500.0]), Some((500.0, 1.0)));
+        assert_eq!(spread(&[50_000.0]), Some((0.0, 0.0)));
+        assert_eq!(spread(&[]), None);
+    }
+}
@@ -0,0 +1,19 @@
+use std::io::IsTerminal;
+
+use indicatif::{ProgressBar, ProgressDrawTarget, ProgressStyle};
+
+/// Progress bar of a batch of `len` requests, drawn on stderr so the results stay
+/// clean. It stays hidden for a single request, with --quiet and when stderr isn't
+/// a terminal
+pub fn batch_progress(len: usize, quiet: bool) -> ProgressBar {
+    if len < 2 || quiet || !std::io::stderr().is_terminal() {
+        return ProgressBar::hidden();
+    }
+
+    let progress = ProgressBar::with_draw_target(Some(len as u64), ProgressDrawTarget::stderr());
+    if let Ok(style) = ProgressStyle::with_template("{bar:40} {pos}/{len} fetched ({elapsed})") {
+        progress.set_style(style);
+    }
+
+    progress
+}
@@ -0,0 +1,200 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use crate::address::classify_address_type;
+use crate::format::{format_btc, format_fiat};
+use crate::output::number_format;
+use crate::{BitcoinAddress, Cli, SATOSHIS_PER_BTC};
+
+/// Whether an address still holds funds
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AddressStatus {
+    Funded,
+    /// Received some bitcoins and spent all of them
+    FullySpent,
+    /// Never received anything
+    Unused,
+}
+
+impl AddressStatus {
+    pub fn of(bitcoin_address: &BitcoinAddress) -> AddressStatus {
+        match (
+            bitcoin_address.final_balance,
+            bitcoin_address.total_received,
+        ) {
+            (0, 0) => AddressStatus::Unused,
+            (0, _) => AddressStatus::FullySpent,
+            _ => AddressStatus::Funded,
+        }
+    }
+}
+
+/// Tells the activity of the address in a sentence, for the ones not used to the
+/// columns of the address table:
+///
+/// "This address has made 2 transactions, received 0.019774 BTC (~$741.53), sent
+/// 0.015 BTC (~$562.50), and currently holds 0.004774 BTC (~$179.03). It appears
+/// to be distributing."
+pub fn explain_address(
+    cli: &Cli,
+    bitcoin_address: &BitcoinAddress,
+    bitcoin_price: Option<f64>,
+    currency: &str,
+) -> String {
+    let amount = |satoshis: u64| {
+        let btc = format_btc(satoshis, cli.btc_precision, cli.round);
+        match bitcoin_price {
+            Some(bitcoin_price) => format!(
+                "{btc} (~{})",
+                format_fiat(
+                    satoshis as f64 / SATOSHIS_PER_BTC as f64 * bitcoin_price,
+                    currency,
+                    &number_format(),
+                    cli.output_precision_usd,
+                )
+            ),
+            None => btc,
+        }
+    };
+
+    let transactions = match bitcoin_address.n_tx {
+        1 => "1 transaction".to_string(),
+        n_tx => format!("{n_tx} transactions"),
+    };
+
+    format!(
+        "This address has made {transactions}, received {}, sent {}, and currently holds {}. {}",
+        amount(bitcoin_address.total_received),
+        amount(bitcoin_address.total_sent),
+        amount(bitcoin_address.final_balance),
+        trend(bitcoin_address)
+    )
+}
+
+/// Whether the address keeps what it receives or passes it on, judged by the share
+/// of everything it received that it still holds
+fn trend(bitcoin_address: &BitcoinAddress) -> &'static str {
+    match AddressStatus::of(bitcoin_address) {
+        AddressStatus::Unused => "It has never been used.",
+        AddressStatus::FullySpent => "It has spent everything it received.",
+        AddressStatus::Funded
+            if bitcoin_address.final_balance * 2 >= bitcoin_address.total_received =>
+        {
+            "It appears to be accumulating."
+        }
+        AddressStatus::Funded => "It appears to be distributing.",
+    }
+}
+
+/// An amount in satoshis, in BTC and in fiat when the price is available
+#[derive(Debug, Serialize)]
+pub struct AmountReport {
+    satoshis: u64,
+    btc: f64,
+    fiat: Option<f64>,
+}
+
+impl AmountReport {
+    fn new(satoshis: u64, bitcoin_price: Option<f64>) -> AmountReport {
+        let btc = satoshis as f64 / SATOSHIS_PER_BTC as f64;
+
+        AmountReport {
+            satoshis,
+            btc,
+            fiat: bitcoin_price.map(|bitcoin_price| btc * bitcoin_price),
+        }
+    }
+}
+
+/// What the address table shows, computed once for the JSON and YAML output: the
+/// amounts in every unit, the address type and the checks made on the raw data
+#[derive(Debug, Serialize)]
+pub struct AddressReport {
+    address: String,
+    address_type: String,
+    n_tx: u64,
+    currency: String,
+    total_received: AmountReport,
+    total_sent: AmountReport,
+    final_balance: AmountReport,
+    status: AddressStatus,
+    // Received minus sent gives the balance, false means the API is inconsistent
+    reconciled: bool,
+    // Within the latest page of the history fetched with the address
+    first_seen: Option<DateTime<Utc>>,
+    last_seen: Option<DateTime<Utc>>,
+}
+
+impl AddressReport {
+    pub fn new(
+        bitcoin_address: &BitcoinAddress,
+        bitcoin_price: Option<f64>,
+        currency: &str,
+    ) -> AddressReport {
+        AddressReport {
+            address: bitcoin_address.address.clone(),
+            address_type: classify_address_type(&bitcoin_address.address).to_string(),
+            n_tx: bitcoin_address.n_tx,
+            currency: currency.to_string(),
+            total_received: AmountReport::new(bitcoin_address.total_received, bitcoin_price),
+            total_sent: AmountReport::new(bitcoin_address.total_sent, bitcoin_price),
+            final_balance: AmountReport::new(bitcoin_address.final_balance, bitcoin_price),
+            status: AddressStatus::of(bitcoin_address),
+            reconciled: bitcoin_address.is_reconciled(),
+            first_seen: bitcoin_address
+                .first_seen()
+                .map(|(first_seen, _)| first_seen),
+            last_seen: bitcoin_address.last_seen(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn report_converts_the_amounts_and_classifies_the_address() {
+        let bitcoin_address: BitcoinAddress = serde_json::from_str(
+            r#"{
+                "address": "bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdq",
+                "n_tx": 2,
+                "total_received": 150000000,
+                "total_sent": 150000000,
+                "final_balance": 0
+            }"#,
+        )
+        .unwrap();
+
+        let report =
+            serde_json::to_value(AddressReport::new(&bitcoin_address, Some(40_000.0), "USD"))
+                .unwrap();
+
+        assert_eq!(report["total_received"]["btc"], 1.5);
+        assert_eq!(report["total_received"]["fiat"], 60_000.0);
+        assert_eq!(report["status"], "fully_spent");
+        assert_eq!(report["reconciled"], true);
+        assert_eq!(report["address_type"], "P2WPKH (Bech32 SegWit)");
+    }
+
+    #[test]
+    fn trend_follows_the_share_still_held() {
+        let address = |received: u64, sent: u64| BitcoinAddress {
+            address: "bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdq".to_string(),
+            n_tx: 2,
+            total_received: received,
+            total_sent: sent,
+            final_balance: received - sent,
+            txs: Vec::new(),
+        };
+
+        assert_eq!(trend(&address(100, 10)), "It appears to be accumulating.");
+        assert_eq!(trend(&address(100, 90)), "It appears to be distributing.");
+        assert_eq!(
+            trend(&address(100, 100)),
+            "It has spent everything it received."
+        );
+        assert_eq!(trend(&address(0, 0)), "It has never been used.");
+    }
+}
@@ -0,0 +1,55 @@
+use std::fs;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::BitcoinAddress;
+
+/// Stats of an address saved at some point in time, to compare them with later runs
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AddressSnapshot {
+    pub taken_at: DateTime<Utc>,
+    pub address: BitcoinAddress,
+}
+
+/// Field-wise difference between the current stats of an address and a snapshot,
+/// all the amounts are in satoshis
+#[derive(Debug, PartialEq, Eq)]
+pub struct AddressDiff {
+    pub new_transactions: i64,
+    pub received: i64,
+    pub sent: i64,
+    pub balance: i64,
+}
+
+impl AddressDiff {
+    pub fn between(before: &BitcoinAddress, now: &BitcoinAddress) -> AddressDiff {
+        AddressDiff {
+            new_transactions: now.n_tx as i64 - before.n_tx as i64,
+            received: now.total_received as i64 - before.total_received as i64,
+            sent: now.total_sent as i64 - before.total_sent as i64,
+            balance: now.final_balance as i64 - before.final_balance as i64,
+        }
+    }
+}
+
+/// Writes the current stats of the address to `path` as JSON
+pub fn save_snapshot(
+    path: &str,
+    bitcoin_address: &BitcoinAddress,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let snapshot = AddressSnapshot {
+        taken_at: Utc::now(),
+        address: bitcoin_address.clone(),
+    };
+
+    fs::write(path, serde_json::to_string_pretty(&snapshot)?)?;
+
+    Ok(())
+}
+
+pub fn load_snapshot(path: &str) -> Result<AddressSnapshot, Box<dyn std::error::Error>> {
+    let snapshot = serde_json::from_str(&fs::read_to_string(path)?)?;
+
+    Ok(snapshot)
+}
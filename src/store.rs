@@ -0,0 +1,129 @@
+use chrono::Utc;
+use rusqlite::{params, Connection};
+
+use crate::{BitcoinAddress, Transaction};
+
+/// Tables of the store, a row per transaction hash and per address. `first_seen`
+/// is when a run fetched it for the first time and `last_seen` the latest one
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS transactions (
+        hash TEXT PRIMARY KEY,
+        total_in INTEGER NOT NULL,
+        total_out INTEGER NOT NULL,
+        fee INTEGER,
+        block_height INTEGER,
+        first_seen TEXT NOT NULL,
+        last_seen TEXT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS addresses (
+        address TEXT PRIMARY KEY,
+        n_tx INTEGER NOT NULL,
+        total_received INTEGER NOT NULL,
+        total_sent INTEGER NOT NULL,
+        final_balance INTEGER NOT NULL,
+        first_seen TEXT NOT NULL,
+        last_seen TEXT NOT NULL
+    );
+";
+
+/// SQLite database the fetched transactions and addresses are kept in with --db,
+/// so the runs build up a dataset that can be queried on its own. The amounts are
+/// in satoshis
+pub struct Store {
+    connection: Connection,
+}
+
+impl Store {
+    /// Opens the database at `path`, creating it and its tables when needed
+    pub fn open(path: &str) -> rusqlite::Result<Store> {
+        let connection = Connection::open(path)?;
+        connection.execute_batch(SCHEMA)?;
+
+        Ok(Store { connection })
+    }
+
+    /// Saves a transaction, one seen before is updated (i.e it got confirmed)
+    pub fn record_transaction(&self, transaction: &Transaction) -> rusqlite::Result<()> {
+        let fee = transaction
+            .fee()
+            .filter(|_| !transaction.is_coinbase())
+            .map(|fee| fee as i64);
+
+        self.connection.execute(
+            "INSERT INTO transactions (hash, total_in, total_out, fee, block_height, first_seen, last_seen)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?6)
+             ON CONFLICT (hash) DO UPDATE SET
+                 total_in = excluded.total_in,
+                 total_out = excluded.total_out,
+                 fee = excluded.fee,
+                 block_height = excluded.block_height,
+                 last_seen = excluded.last_seen",
+            params![
+                transaction.hash,
+                transaction.total_input() as i64,
+                transaction.total_output() as i64,
+                fee,
+                transaction.block_height.map(|height| height as i64),
+                Utc::now().to_rfc3339(),
+            ],
+        )?;
+
+        Ok(())
+    }
+
+    /// Saves an address with its current totals, replacing the ones of a previous run
+    pub fn record_address(&self, bitcoin_address: &BitcoinAddress) -> rusqlite::Result<()> {
+        self.connection.execute(
+            "INSERT INTO addresses (address, n_tx, total_received, total_sent, final_balance, first_seen, last_seen)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?6)
+             ON CONFLICT (address) DO UPDATE SET
+                 n_tx = excluded.n_tx,
+                 total_received = excluded.total_received,
+                 total_sent = excluded.total_sent,
+                 final_balance = excluded.final_balance,
+                 last_seen = excluded.last_seen",
+            params![
+                bitcoin_address.address,
+                bitcoin_address.n_tx as i64,
+                bitcoin_address.total_received as i64,
+                bitcoin_address.total_sent as i64,
+                bitcoin_address.final_balance as i64,
+                Utc::now().to_rfc3339(),
+            ],
+        )?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_transaction_seen_twice_is_stored_once() {
+        let store = Store::open(":memory:").unwrap();
+        let mut transaction: Transaction = serde_json::from_str(
+            r#"{
+                "hash": "abc",
+                "inputs": [{"prev_out": {"value": 100000}}],
+                "out": [{"value": 90000}]
+            }"#,
+        )
+        .unwrap();
+
+        store.record_transaction(&transaction).unwrap();
+        transaction.block_height = Some(820_000);
+        store.record_transaction(&transaction).unwrap();
+
+        let (count, fee, block_height): (i64, i64, i64) = store
+            .connection
+            .query_row(
+                "SELECT COUNT(*), MAX(fee), MAX(block_height) FROM transactions",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+            .unwrap();
+        assert_eq!((count, fee, block_height), (1, 10_000, 820_000));
+    }
+}
@@ -0,0 +1,112 @@
+use std::time::Instant;
+
+use ansi_term::Color;
+use cli_table::{Cell, Style, Table};
+use serde::Serialize;
+
+use crate::format::{format_btc, RoundingMode};
+use crate::output::{amount_cell, paint, print_table, summary_border, summary_style};
+use crate::Transaction;
+
+/// Wrap-up of a batch run, updated as each item completes
+#[derive(Debug, Serialize)]
+pub struct BatchSummary {
+    pub total_items: usize,
+    pub succeeded: usize,
+    pub failed: usize,
+    // Only batches of transactions move bitcoins and pay fees, in satoshis
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total_moved: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total_fees: Option<u64>,
+    pub elapsed_seconds: f64,
+    #[serde(skip)]
+    started: Instant,
+}
+
+impl BatchSummary {
+    pub fn new(total_items: usize) -> BatchSummary {
+        BatchSummary {
+            total_items,
+            succeeded: 0,
+            failed: 0,
+            total_moved: None,
+            total_fees: None,
+            elapsed_seconds: 0.0,
+            started: Instant::now(),
+        }
+    }
+
+    pub fn record_success(&mut self) {
+        self.succeeded += 1;
+        self.elapsed_seconds = self.started.elapsed().as_secs_f64();
+    }
+
+    pub fn record_transaction(&mut self, transaction: &Transaction) {
+        *self.total_moved.get_or_insert(0) += transaction.total_output();
+        *self.total_fees.get_or_insert(0) += transaction.fee().unwrap_or(0);
+        self.record_success();
+    }
+
+    pub fn record_failure(&mut self) {
+        self.failed += 1;
+        self.elapsed_seconds = self.started.elapsed().as_secs_f64();
+    }
+
+    /// Exit code of the run once the summary is out, a failure of any item fails it
+    pub fn exit_code(&self) -> i32 {
+        if self.failed == 0 {
+            0
+        } else {
+            1
+        }
+    }
+
+    /// Footer table shown at the end of a batch in table mode
+    pub fn print_table(
+        &self,
+        btc_precision: u32,
+        round: RoundingMode,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let mut title = vec![
+            "Items".cell().bold(true),
+            "Succeeded".cell().bold(true),
+            "Failed".cell().bold(true),
+        ];
+        let total_style = summary_style(Color::Green);
+        let mut row = vec![
+            paint(total_style, self.total_items.to_string()).cell(),
+            paint(Color::Green, self.succeeded.to_string()).cell(),
+            if self.failed == 0 {
+                paint(total_style, "0").cell()
+            } else {
+                paint(Color::Red, self.failed.to_string()).cell()
+            },
+        ];
+
+        if let (Some(total_moved), Some(total_fees)) = (self.total_moved, self.total_fees) {
+            title.push("BTC Moved".cell().bold(true));
+            title.push("Total Fees".cell().bold(true));
+            row.push(amount_cell(
+                total_style,
+                format_btc(total_moved, btc_precision, round),
+            ));
+            row.push(amount_cell(
+                total_style,
+                format_btc(total_fees, btc_precision, round),
+            ));
+        }
+
+        title.push("Elapsed".cell().bold(true));
+        row.push(paint(total_style, format!("{:.2}s", self.elapsed_seconds)).cell());
+
+        print_table(
+            vec![row]
+                .table()
+                .title(title)
+                .foreground_color(summary_border(Color::Green)),
+        )?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,100 @@
+use serde_json::Value;
+
+use crate::Transaction;
+
+/// Compares a parsed transaction, serialized back to JSON, with the raw response
+/// of the API, so fields silently dropped by the parsing don't go unnoticed.
+///
+/// Returns a description of every mismatch, none when the parsing kept everything.
+pub fn verify_round_trip(
+    raw_json: &str,
+    transaction: &Transaction,
+) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let raw: Value = serde_json::from_str(raw_json)?;
+    let parsed = serde_json::to_value(transaction)?;
+
+    let mut mismatches = Vec::new();
+
+    let raw_inputs = sum_values(&raw["inputs"], |input| &input["prev_out"]["value"]);
+    let parsed_inputs = sum_values(&parsed["inputs"], |input| &input["prev_out"]["value"]);
+    if raw_inputs != parsed_inputs {
+        mismatches.push(format!(
+            "the inputs add up to {raw_inputs} sats in the API response but to {parsed_inputs} sats once parsed"
+        ));
+    }
+
+    let raw_outputs = sum_values(&raw["out"], |output| &output["value"]);
+    let parsed_outputs = sum_values(&parsed["out"], |output| &output["value"]);
+    if raw_outputs != parsed_outputs {
+        mismatches.push(format!(
+            "the outputs add up to {raw_outputs} sats in the API response but to {parsed_outputs} sats once parsed"
+        ));
+    }
+
+    // The counts and the fee are only checked when the response includes them
+    if let Some(vin_sz) = raw["vin_sz"].as_u64() {
+        if vin_sz != transaction.inputs.len() as u64 {
+            mismatches.push(format!(
+                "the API reports {vin_sz} inputs but {} were parsed",
+                transaction.inputs.len()
+            ));
+        }
+    }
+
+    if let Some(vout_sz) = raw["vout_sz"].as_u64() {
+        if vout_sz != transaction.out.len() as u64 {
+            mismatches.push(format!(
+                "the API reports {vout_sz} outputs but {} were parsed",
+                transaction.out.len()
+            ));
+        }
+    }
+
+    if let Some(fee) = raw["fee"].as_u64().filter(|_| !transaction.is_coinbase()) {
+        if Some(fee) != transaction.fee() {
+            mismatches.push(format!(
+                "the API reports a fee of {fee} sats but the parsed inputs and outputs give {}",
+                transaction
+                    .fee()
+                    .map(|fee| format!("{fee} sats"))
+                    .unwrap_or_else(|| "a negative fee".to_string())
+            ));
+        }
+    }
+
+    Ok(mismatches)
+}
+
+/// Sums the `value` picked from every element of a JSON array
+fn sum_values(list: &Value, value: impl Fn(&Value) -> &Value) -> u64 {
+    list.as_array()
+        .map(|list| list.iter().filter_map(|item| value(item).as_u64()).sum())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_what_the_parsing_dropped() {
+        let raw_json = r#"{
+            "hash": "abc",
+            "vin_sz": 1,
+            "vout_sz": 2,
+            "fee": 1000,
+            "inputs": [{"prev_out": {"value": 10000}}],
+            "out": [{"value": 4000}, {"value": 5000}]
+        }"#;
+
+        let transaction: Transaction = serde_json::from_str(raw_json).unwrap();
+        assert!(verify_round_trip(raw_json, &transaction)
+            .unwrap()
+            .is_empty());
+
+        let mut transaction = transaction;
+        transaction.out.pop();
+        let mismatches = verify_round_trip(raw_json, &transaction).unwrap();
+        assert_eq!(mismatches.len(), 3);
+    }
+}
@@ -0,0 +1,405 @@
+use std::collections::HashSet;
+use std::io::Write;
+use std::time::Duration;
+
+use ansi_term::Color;
+use chrono::{DateTime, Local};
+use cli_table::{Cell, Style, Table, TableStruct};
+use notify_rust::Notification;
+use num_format::ToFormattedString;
+
+use crate::format::{format_btc, format_fiat};
+use crate::http::HttpClient;
+use crate::output::{amount_cell, number_format, paint, print_table, summary_style};
+use crate::snapshot::AddressDiff;
+use crate::{
+    check_price_age, fetch_validated_price, inspect_address, time_format, unconfirmed_count,
+    unconfirmed_hash_label, unconfirmed_row_color, unconfirmed_transactions, BitcoinAddress, Cli,
+    UndefinedTransaction, SATOSHIS_PER_BTC,
+};
+
+/// Polls the address every `interval` and reports when its balance or number of
+/// transactions changes, until the user presses Ctrl-C.
+///
+/// With --notify a payment arriving also rings the terminal bell and shows a
+/// desktop notification, when the desktop supports them.
+pub async fn watch_address(
+    cli: &Cli,
+    http: &HttpClient,
+    address: &str,
+    inspect_address_url: &str,
+    bitcoin_price_url: Option<&str>,
+    currency: &str,
+    interval: Duration,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut previous: Option<BitcoinAddress> = None;
+    let mut desktop_notifications = true;
+
+    loop {
+        let now = chrono::offset::Local::now().format("%H:%M:%S");
+
+        match inspect_address(address, inspect_address_url, http).await {
+            Ok(current) => {
+                match &previous {
+                    None => println!(
+                        "{} {}",
+                        paint(Color::Purple, format!("[{now}]")),
+                        paint(Color::Cyan, format!(
+                            "Watching {address}: balance {}, {} transactions (every {}s, Ctrl-C to stop)",
+                            format_btc(current.final_balance, cli.btc_precision, cli.round),
+                            current.n_tx,
+                            interval.as_secs()
+                        ))
+                    ),
+                    Some(previous) => {
+                        let diff = AddressDiff::between(previous, &current);
+                        report_changes(cli, &now.to_string(), &diff, &current);
+
+                        if cli.notify && diff.balance > 0 {
+                            // Without a price the notification shows only the BTC amount
+                            let quote = fetch_validated_price(http, bitcoin_price_url, currency, cli.format)
+                                .await
+                                .ok()
+                                .flatten();
+                            if let Some(quote) = &quote {
+                                check_price_age(cli, quote, currency);
+                            }
+                            let bitcoin_price = quote.map(|quote| quote.price);
+                            let received = match bitcoin_price {
+                                Some(bitcoin_price) => format!(
+                                    "{} ({})",
+                                    format_btc(diff.balance, cli.btc_precision, cli.round),
+                                    format_fiat(
+                                        diff.balance as f64 / SATOSHIS_PER_BTC as f64
+                                            * bitcoin_price,
+                                        currency,
+                                        &number_format(),
+                                        cli.output_precision_usd,
+                                    )
+                                ),
+                                None => format_btc(diff.balance, cli.btc_precision, cli.round),
+                            };
+
+                            notify_payment(address, &received, &mut desktop_notifications);
+                        }
+                    }
+                }
+
+                previous = Some(current);
+            }
+            // A failed poll doesn't stop the watch, the next one may work
+            Err(error) => eprintln!(
+                "{} {}",
+                paint(Color::Purple, format!("[{now}]")),
+                paint(
+                    Color::Red,
+                    format!("[!] Couldn't fetch the address {address}: {error}")
+                )
+            ),
+        }
+
+        tokio::time::sleep(interval).await;
+    }
+}
+
+/// What the unconfirmed watch has seen since it started, across every refresh
+#[derive(Debug)]
+pub struct SessionStats {
+    started: DateTime<Local>,
+    seen: HashSet<String>,
+    amount_satoshis: u64,
+    peak_mempool: Option<u64>,
+}
+
+impl SessionStats {
+    pub fn new(started: DateTime<Local>) -> SessionStats {
+        SessionStats {
+            started,
+            seen: HashSet::new(),
+            amount_satoshis: 0,
+            peak_mempool: None,
+        }
+    }
+
+    /// Adds the transactions of a refresh, a transaction still in the feed from a
+    /// previous refresh isn't counted twice. Returns the ones seen for the first time
+    pub fn record<'a>(
+        &mut self,
+        undefined_transactions: &'a [UndefinedTransaction],
+    ) -> Vec<&'a UndefinedTransaction> {
+        let new: Vec<&UndefinedTransaction> = undefined_transactions
+            .iter()
+            .filter(|undefined_transaction| self.seen.insert(undefined_transaction.hash.clone()))
+            .collect();
+
+        self.amount_satoshis += new
+            .iter()
+            .map(|undefined_transaction| undefined_transaction.amount_satoshis)
+            .sum::<u64>();
+
+        new
+    }
+
+    pub fn record_mempool_count(&mut self, count: u64) {
+        self.peak_mempool = Some(self.peak_mempool.map_or(count, |peak| peak.max(count)));
+    }
+}
+
+/// Refreshes the unconfirmed feed every `interval` and lists the transactions that
+/// arrived since the previous refresh, until the user presses Ctrl-C.
+///
+/// With --stats-interval the totals of the whole session (transactions seen, BTC
+/// observed and the peak size of the mempool) are shown every that many refreshes.
+pub async fn watch_unconfirmed(
+    cli: &Cli,
+    http: &HttpClient,
+    unconfirmed_transactions_url: &str,
+    unconfirmed_count_url: &str,
+    bitcoin_price_url: Option<&str>,
+    interval: Duration,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut stats = SessionStats::new(chrono::offset::Local::now());
+    let currency = cli.currency.to_uppercase();
+    let number_outputs = cli.number_outputs.unwrap_or(100);
+
+    println!(
+        "{}",
+        paint(
+            Color::Yellow,
+            format!(
+                "Watching the mempool (every {}s, Ctrl-C to stop)\n",
+                interval.as_secs()
+            )
+        )
+    );
+
+    for refresh in 1.. {
+        let now = chrono::offset::Local::now().format("%H:%M:%S");
+
+        // The watch lists BTC amounts only, so the price is fetched on every refresh
+        // only to check its age with --max-price-age
+        match unconfirmed_transactions(
+            http,
+            unconfirmed_transactions_url,
+            bitcoin_price_url.filter(|_| cli.max_price_age.is_some()),
+            &currency,
+            number_outputs,
+            cli.exclude_change,
+            cli.format,
+        )
+        .await
+        {
+            Ok((undefined_transactions, quote)) => {
+                if let Some(quote) = &quote {
+                    check_price_age(cli, quote, &currency);
+                }
+
+                let new = stats.record(&undefined_transactions);
+                println!(
+                    "{} {}",
+                    paint(Color::Purple, format!("[{now}]")),
+                    paint(
+                        Color::Yellow,
+                        format!("{} new unconfirmed transactions", new.len())
+                    )
+                );
+
+                if !new.is_empty() && !cli.summary {
+                    print_table(new_transactions_table(cli, &new))?;
+                }
+            }
+            // A failed refresh doesn't stop the watch, the next one may work
+            Err(error) => eprintln!(
+                "{} {}",
+                paint(Color::Purple, format!("[{now}]")),
+                paint(
+                    Color::Red,
+                    format!("[!] Couldn't fetch the unconfirmed transactions: {error}")
+                )
+            ),
+        }
+
+        if let Some(stats_interval) = cli.stats_interval {
+            if let Ok(count) = unconfirmed_count(http, unconfirmed_count_url).await {
+                stats.record_mempool_count(count);
+            }
+
+            if refresh % stats_interval == 0 {
+                print_session_stats(cli, &stats);
+            }
+        }
+
+        tokio::time::sleep(interval).await;
+    }
+
+    Ok(())
+}
+
+/// Table of the transactions that arrived since the previous refresh, the double
+/// spends in red like in the unconfirmed table
+pub fn new_transactions_table(cli: &Cli, new: &[&UndefinedTransaction]) -> TableStruct {
+    let table: Vec<_> = new
+        .iter()
+        .map(|undefined_transaction| {
+            let color = unconfirmed_row_color(undefined_transaction);
+
+            vec![
+                paint(color, unconfirmed_hash_label(cli, undefined_transaction)).cell(),
+                amount_cell(
+                    color,
+                    format_btc(
+                        undefined_transaction.amount_satoshis,
+                        cli.btc_precision,
+                        cli.round,
+                    ),
+                ),
+                paint(color, undefined_transaction.time.format(time_format(cli))).cell(),
+            ]
+        })
+        .collect();
+
+    table
+        .table()
+        .title(vec![
+            "Hash".cell().bold(true),
+            "Bitcoin".cell().bold(true),
+            "Time".cell().bold(true),
+        ])
+        .foreground_color(Some(cli_table::Color::Yellow))
+}
+
+/// Footer with the totals of the session so far
+fn print_session_stats(cli: &Cli, stats: &SessionStats) {
+    let peak_mempool = stats
+        .peak_mempool
+        .map(|peak| peak.to_formatted_string(&number_format()))
+        .unwrap_or_else(|| "unknown".to_string());
+
+    // Follows --summary-color like the total rows under the tables
+    let total_style = summary_style(Color::Yellow);
+    println!(
+        "{} {}\n",
+        paint(
+            total_style.bold(),
+            format!("Since {}:", stats.started.format(time_format(cli)))
+        ),
+        paint(
+            total_style,
+            format!(
+                "{} transactions seen, {} observed, peak mempool of {} transactions",
+                stats.seen.len().to_formatted_string(&number_format()),
+                format_btc(stats.amount_satoshis, cli.btc_precision, cli.round),
+                peak_mempool
+            )
+        )
+    );
+}
+
+/// Rings the terminal bell and shows a desktop notification for a payment, once the
+/// desktop fails to show one only the bell is used
+fn notify_payment(address: &str, received: &str, desktop_notifications: &mut bool) {
+    print!("\x07");
+    let _ = std::io::stdout().flush();
+
+    if !*desktop_notifications {
+        return;
+    }
+
+    let shown = Notification::new()
+        .summary("Bitcoin received")
+        .body(&format!("{address} received +{received}"))
+        .show();
+
+    if let Err(error) = shown {
+        eprintln!(
+            "{}",
+            paint(
+                Color::Yellow,
+                format!(
+                "[!] Desktop notifications aren't available ({error}), using the terminal bell only"
+            )
+            )
+        );
+        *desktop_notifications = false;
+    }
+}
+
+/// Prints what changed since the previous poll, in green what went up and in red
+/// what went down
+fn report_changes(cli: &Cli, now: &str, diff: &AddressDiff, current: &BitcoinAddress) {
+    if diff.balance == 0 && diff.new_transactions == 0 {
+        return;
+    }
+
+    let highlight = |change: i64, text: String| {
+        if change > 0 {
+            paint(Color::Green.bold(), text)
+        } else if change < 0 {
+            paint(Color::Red.bold(), text)
+        } else {
+            paint(Color::Cyan, text)
+        }
+    };
+
+    let balance_change = format_btc(diff.balance, cli.btc_precision, cli.round);
+    let sign = if diff.balance > 0 { "+" } else { "" };
+
+    println!(
+        "{} {} {}",
+        paint(Color::Purple, format!("[{now}]")),
+        highlight(
+            diff.balance,
+            format!(
+                "balance {sign}{balance_change} (now {}),",
+                format_btc(current.final_balance, cli.btc_precision, cli.round)
+            )
+        ),
+        highlight(
+            diff.new_transactions,
+            format!(
+                "{:+} transactions (now {})",
+                diff.new_transactions, current.n_tx
+            )
+        ),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn session_counts_every_transaction_once() {
+        let undefined_transaction = |hash: &str, amount_satoshis| UndefinedTransaction {
+            hash: hash.to_string(),
+            amount_satoshis,
+            amount_fiat: None,
+            time: Local::now(),
+            relayed_by: "unknown".to_string(),
+            double_spend: false,
+            fee_rate: None,
+            net_flow_satoshis: amount_satoshis,
+            sender: None,
+        };
+
+        let mut stats = SessionStats::new(Local::now());
+        let first = [
+            undefined_transaction("a", 1_000),
+            undefined_transaction("b", 2_000),
+        ];
+        assert_eq!(stats.record(&first).len(), 2);
+
+        let second = [
+            undefined_transaction("b", 2_000),
+            undefined_transaction("c", 4_000),
+        ];
+        assert_eq!(stats.record(&second).len(), 1);
+
+        stats.record_mempool_count(3_000);
+        stats.record_mempool_count(2_500);
+
+        assert_eq!(stats.seen.len(), 3);
+        assert_eq!(stats.amount_satoshis, 7_000);
+        assert_eq!(stats.peak_mempool, Some(3_000));
+    }
+}
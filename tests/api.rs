@@ -0,0 +1,655 @@
+//! Runs the tool against a local mock of the blockchain.info API, pointed at it
+//! with --api-base, and checks what it parsed through the --format json output
+
+use std::process::Output;
+use std::time::Duration;
+
+use serde_json::Value;
+use tokio::process::Command;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+const TRANSACTION_HASH: &str = "b6f6991d03df0e2e04dafffcd6bc418aac66049e2cd74b80f14ac86db1e3f0da";
+const ADDRESS: &str = "bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdq";
+
+/// Serves the fixture `name` from tests/fixtures at `endpoint`
+async fn mock_fixture(server: &MockServer, endpoint: &str, name: &str) {
+    let body = std::fs::read_to_string(format!(
+        "{}/tests/fixtures/{name}",
+        env!("CARGO_MANIFEST_DIR")
+    ))
+    .unwrap();
+
+    Mock::given(method("GET"))
+        .and(path(endpoint))
+        .respond_with(ResponseTemplate::new(200).set_body_string(body))
+        .mount(server)
+        .await;
+}
+
+async fn btc_analyser(server: &MockServer, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_btcAnalyser"))
+        .args(args)
+        .args(["--api-base", &server.uri(), "--format", "json"])
+        .env_remove("BTC_ANALYSER_CURRENCY")
+        .env_remove("BTC_ANALYSER_API_BASE")
+        .env_remove("BTC_ANALYSER_TIMEOUT")
+        .output()
+        .await
+        .unwrap()
+}
+
+fn stdout_json(output: &Output) -> Value {
+    assert!(output.status.success(), "{output:?}");
+    serde_json::from_slice(&output.stdout).unwrap()
+}
+
+#[tokio::test]
+async fn inspects_a_transaction() {
+    let server = MockServer::start().await;
+    mock_fixture(
+        &server,
+        &format!("/rawtx/{TRANSACTION_HASH}"),
+        "transaction.json",
+    )
+    .await;
+
+    let transaction =
+        stdout_json(&btc_analyser(&server, &["-e", "inspect", "-i", TRANSACTION_HASH]).await);
+
+    assert_eq!(transaction["hash"], TRANSACTION_HASH);
+    assert_eq!(transaction["inputs"][0]["prev_out"]["value"], 1_500_000);
+    assert_eq!(
+        transaction["out"][0]["addr"],
+        "1A1zP1eP5QGefi2DMPTfTL5SLmv7DivfNa"
+    );
+    assert_eq!(transaction["out"][1]["value"], 477_400);
+}
+
+#[tokio::test]
+async fn messagepack_holds_the_same_fields_as_the_json() {
+    let server = MockServer::start().await;
+    mock_fixture(
+        &server,
+        &format!("/rawtx/{TRANSACTION_HASH}"),
+        "transaction.json",
+    )
+    .await;
+
+    let output = Command::new(env!("CARGO_BIN_EXE_btcAnalyser"))
+        .args(["-e", "inspect", "-i", TRANSACTION_HASH])
+        .args(["--api-base", &server.uri(), "--format", "messagepack"])
+        .output()
+        .await
+        .unwrap();
+    assert!(output.status.success(), "{output:?}");
+
+    let transaction: Value = rmp_serde::from_slice(&output.stdout).unwrap();
+    assert_eq!(transaction["hash"], TRANSACTION_HASH);
+    assert_eq!(transaction["out"][1]["value"], 477_400);
+}
+
+#[tokio::test]
+async fn compact_json_fits_on_a_line() {
+    let server = MockServer::start().await;
+    mock_fixture(
+        &server,
+        &format!("/rawtx/{TRANSACTION_HASH}"),
+        "transaction.json",
+    )
+    .await;
+
+    let output = btc_analyser(
+        &server,
+        &["-e", "inspect", "-i", TRANSACTION_HASH, "--json-compact"],
+    )
+    .await;
+
+    assert_eq!(String::from_utf8_lossy(&output.stdout).lines().count(), 1);
+    assert_eq!(stdout_json(&output)["hash"], TRANSACTION_HASH);
+}
+
+#[tokio::test]
+async fn coinbase_inputs_have_no_previous_output() {
+    let server = MockServer::start().await;
+    mock_fixture(&server, "/rawtx/coinbase", "coinbase.json").await;
+
+    let transaction =
+        stdout_json(&btc_analyser(&server, &["-e", "inspect", "-i", "coinbase"]).await);
+
+    assert_eq!(transaction["inputs"][0]["prev_out"], Value::Null);
+    assert_eq!(transaction["out"][0]["value"], 5_000_000_000u64);
+}
+
+#[tokio::test]
+async fn op_return_outputs_have_no_address() {
+    let server = MockServer::start().await;
+    mock_fixture(&server, "/rawtx/op_return", "op_return.json").await;
+
+    let transaction =
+        stdout_json(&btc_analyser(&server, &["-e", "inspect", "-i", "op_return"]).await);
+
+    assert_eq!(transaction["out"][0]["addr"], Value::Null);
+    assert_eq!(transaction["out"][0]["value"], 0);
+    assert_eq!(
+        transaction["out"][1]["addr"],
+        "3J98t1WpEZ73CNmQviecrnyiWrnqRhWNLy"
+    );
+}
+
+#[tokio::test]
+async fn unknown_transaction_is_reported() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/rawtx/missing"))
+        .respond_with(ResponseTemplate::new(404).set_body_string("Transaction not found"))
+        .mount(&server)
+        .await;
+
+    let output = btc_analyser(&server, &["-e", "inspect", "-i", "missing"]).await;
+
+    assert!(!output.status.success());
+    assert!(output.stdout.is_empty());
+    assert!(String::from_utf8_lossy(&output.stderr)
+        .contains("There is not transaction with the hash missing"));
+}
+
+#[tokio::test]
+async fn batch_keeps_going_but_fails_on_a_failed_item() {
+    let server = MockServer::start().await;
+    mock_fixture(
+        &server,
+        &format!("/rawtx/{TRANSACTION_HASH}"),
+        "transaction.json",
+    )
+    .await;
+    mock_fixture(&server, &format!("/rawaddr/{ADDRESS}"), "address.json").await;
+
+    // The summary is still printed, then the run fails
+    let output = btc_analyser(
+        &server,
+        &["-e", "inspect", "-i", TRANSACTION_HASH, "-i", "missing"],
+    )
+    .await;
+    assert!(!output.status.success());
+    let batch: Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(batch["summary"]["succeeded"], 1);
+    assert_eq!(batch["summary"]["failed"], 1);
+
+    let unknown = "bc1qxy2kgdygjrsqtzq2n0yrf2493p83kkfjhx0wlh";
+    let output = btc_analyser(&server, &["-e", "address", "-a", ADDRESS, "-a", unknown]).await;
+    assert!(!output.status.success());
+    let batch: Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(batch["summary"]["failed"], 1);
+}
+
+#[tokio::test]
+async fn api_error_is_not_reported_as_an_unknown_transaction() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/rawtx/broken"))
+        .respond_with(ResponseTemplate::new(500))
+        .mount(&server)
+        .await;
+
+    let output = btc_analyser(&server, &["-e", "inspect", "-i", "broken"]).await;
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("Couldn't fetch broken: the API answered 500"),
+        "{stderr}"
+    );
+    assert!(!stderr.contains("There is not transaction"));
+}
+
+#[tokio::test]
+async fn rejected_taproot_address_points_to_another_explorer() {
+    let taproot = "bc1p5d7rjq7g6rdk2yhzks9smlaqtedr4dekq08ge8ztwac72sfr9rusxg3297";
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path(format!("/rawaddr/{taproot}")))
+        .respond_with(ResponseTemplate::new(400).set_body_string("Invalid Bitcoin Address"))
+        .mount(&server)
+        .await;
+
+    let output = btc_analyser(&server, &["-e", "address", "-a", taproot]).await;
+
+    assert!(String::from_utf8_lossy(&output.stderr)
+        .contains("this explorer may not support Taproot addresses"));
+}
+
+#[tokio::test]
+async fn unused_address_is_not_an_error() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path(format!("/rawaddr/{ADDRESS}")))
+        .respond_with(ResponseTemplate::new(200).set_body_string(format!(
+            r#"{{"address": "{ADDRESS}", "n_tx": 0, "total_received": 0, "total_sent": 0, "final_balance": 0, "txs": []}}"#
+        )))
+        .mount(&server)
+        .await;
+
+    let output = Command::new(env!("CARGO_BIN_EXE_btcAnalyser"))
+        .args(["-e", "address", "-a", ADDRESS, "--no-usd"])
+        .args(["--api-base", &server.uri()])
+        .output()
+        .await
+        .unwrap();
+    assert!(output.status.success(), "{output:?}");
+    assert!(String::from_utf8_lossy(&output.stdout).contains("no transactions yet"));
+
+    // Unlike an address that couldn't be fetched
+    let missing = "bc1q9jk7j04lcjzdns6wjegeju78mxq27reg5e4wkycrs407yd0r07psvs8x7u";
+    let output = btc_analyser(&server, &["-e", "address", "-a", missing]).await;
+    assert!(!output.status.success());
+}
+
+#[tokio::test]
+async fn ip_ban_page_is_reported_as_such() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/q/unconfirmedcount"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_string("<html><h1>Your IP has been blocked</h1></html>"),
+        )
+        .mount(&server)
+        .await;
+
+    let output = btc_analyser(&server, &["-e", "count"]).await;
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("--limit-rate"));
+
+    // Nor is the page handed over as the raw JSON
+    let output = btc_analyser(&server, &["-e", "count", "--raw-json"]).await;
+
+    assert!(!output.status.success());
+    assert!(output.stdout.is_empty());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("--proxy"));
+}
+
+#[tokio::test]
+async fn inspects_an_address() {
+    let server = MockServer::start().await;
+    mock_fixture(&server, &format!("/rawaddr/{ADDRESS}"), "address.json").await;
+
+    let bitcoin_address =
+        stdout_json(&btc_analyser(&server, &["-e", "address", "-a", ADDRESS]).await);
+
+    assert_eq!(bitcoin_address["address"], ADDRESS);
+    assert_eq!(bitcoin_address["n_tx"], 2);
+    assert_eq!(bitcoin_address["total_received"], 1_977_400);
+    assert_eq!(bitcoin_address["total_sent"], 1_500_000);
+    assert_eq!(bitcoin_address["final_balance"], 477_400);
+}
+
+#[tokio::test]
+async fn lists_unconfirmed_transactions() {
+    let server = MockServer::start().await;
+    mock_fixture(&server, "/unconfirmed-transactions", "unconfirmed.json").await;
+    mock_fixture(&server, "/stats", "stats.json").await;
+
+    let undefined_transactions =
+        stdout_json(&btc_analyser(&server, &["-e", "unconfirmed_transactions"]).await);
+
+    assert_eq!(undefined_transactions.as_array().unwrap().len(), 2);
+
+    let first = &undefined_transactions[0];
+    assert_eq!(first["amount_satoshis"], 195_000);
+    assert_eq!(first["amount_fiat"], 97.5);
+    assert_eq!(first["net_flow_satoshis"], 150_000);
+    assert_eq!(first["fee_rate"], 20.0);
+
+    let second = &undefined_transactions[1];
+    assert_eq!(second["double_spend"], true);
+    assert_eq!(second["fee_rate"], 5.0);
+}
+
+#[tokio::test]
+async fn zero_price_falls_back_to_btc_only() {
+    let server = MockServer::start().await;
+    mock_fixture(&server, "/unconfirmed-transactions", "unconfirmed.json").await;
+    mock_fixture(&server, &format!("/rawaddr/{ADDRESS}"), "address.json").await;
+    Mock::given(method("GET"))
+        .and(path("/stats"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(r#"{"market_price_usd": 0}"#))
+        .mount(&server)
+        .await;
+
+    // The warning stays out of the JSON
+    let output = btc_analyser(&server, &["-e", "unconfirmed_transactions"]).await;
+    let undefined_transactions = stdout_json(&output);
+    assert_eq!(undefined_transactions[0]["amount_satoshis"], 195_000);
+    assert_eq!(undefined_transactions[0]["amount_fiat"], Value::Null);
+    assert!(String::from_utf8_lossy(&output.stderr).contains("invalid bitcoin price"));
+
+    let report =
+        stdout_json(&btc_analyser(&server, &["-e", "address", "-a", ADDRESS, "--summary"]).await);
+    assert_eq!(report["total_received"]["satoshis"], 1_977_400);
+    assert_eq!(report["total_received"]["fiat"], Value::Null);
+}
+
+#[tokio::test]
+async fn exports_the_address_ledger() {
+    let server = MockServer::start().await;
+    mock_fixture(&server, &format!("/rawaddr/{ADDRESS}"), "address.json").await;
+
+    let path = std::env::temp_dir().join(format!("btc-analyser-ledger-{}.csv", std::process::id()));
+    let output = btc_analyser(
+        &server,
+        &[
+            "-e",
+            "address",
+            "-a",
+            ADDRESS,
+            "--csv",
+            path.to_str().unwrap(),
+        ],
+    )
+    .await;
+    assert!(output.status.success(), "{output:?}");
+
+    let ledger = std::fs::read_to_string(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    let lines: Vec<&str> = ledger.lines().collect();
+    assert_eq!(lines[0], "hash,time,value_in,value_out,balance");
+    assert!(lines[1].ends_with(",0.01500000,0.00000000,0.01500000"));
+    assert!(lines[2].ends_with(",0.00477400,0.01500000,0.00477400"));
+}
+
+#[tokio::test]
+async fn no_usd_skips_the_price_request() {
+    let server = MockServer::start().await;
+    mock_fixture(&server, "/unconfirmed-transactions", "unconfirmed.json").await;
+
+    // There is no /stats mock, a price request would fail the command
+    let undefined_transactions =
+        stdout_json(&btc_analyser(&server, &["-e", "unconfirmed_transactions", "--no-usd"]).await);
+
+    assert_eq!(undefined_transactions[0]["amount_fiat"], Value::Null);
+}
+
+#[tokio::test]
+async fn auto_mode_detects_hashes_and_addresses() {
+    let server = MockServer::start().await;
+    mock_fixture(
+        &server,
+        &format!("/rawtx/{TRANSACTION_HASH}"),
+        "transaction.json",
+    )
+    .await;
+    mock_fixture(&server, &format!("/rawaddr/{ADDRESS}"), "address.json").await;
+
+    let transaction =
+        stdout_json(&btc_analyser(&server, &["-e", "auto", "--input", TRANSACTION_HASH]).await);
+    assert_eq!(transaction["hash"], TRANSACTION_HASH);
+
+    let bitcoin_address =
+        stdout_json(&btc_analyser(&server, &["-e", "auto", "--input", ADDRESS]).await);
+    assert_eq!(bitcoin_address["address"], ADDRESS);
+
+    let output = btc_analyser(&server, &["-e", "auto", "--input", "nonsense"]).await;
+    assert!(!output.status.success());
+}
+
+#[tokio::test]
+async fn repeated_hashes_are_fetched_once() {
+    let server = MockServer::start().await;
+    let body = std::fs::read_to_string(format!(
+        "{}/tests/fixtures/transaction.json",
+        env!("CARGO_MANIFEST_DIR")
+    ))
+    .unwrap();
+    Mock::given(method("GET"))
+        .and(path(format!("/rawtx/{TRANSACTION_HASH}")))
+        .respond_with(ResponseTemplate::new(200).set_body_string(body))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let hashes = format!("{TRANSACTION_HASH},{TRANSACTION_HASH}");
+    let batch = stdout_json(&btc_analyser(&server, &["-e", "inspect", "-i", &hashes]).await);
+
+    assert_eq!(batch["transactions"].as_array().unwrap().len(), 2);
+    assert_eq!(batch["summary"]["succeeded"], 2);
+}
+
+#[tokio::test]
+async fn batch_is_fetched_concurrently_and_shown_in_order() {
+    let server = MockServer::start().await;
+    let fixture: Value = serde_json::from_str(
+        &std::fs::read_to_string(format!(
+            "{}/tests/fixtures/transaction.json",
+            env!("CARGO_MANIFEST_DIR")
+        ))
+        .unwrap(),
+    )
+    .unwrap();
+
+    // The first one answers last, each total output tells them apart
+    let hashes = ["a".repeat(64), "b".repeat(64), "c".repeat(64)];
+    for (index, (hash, delay)) in hashes.iter().zip([2, 1, 1]).enumerate() {
+        let mut body = fixture.clone();
+        body["hash"] = Value::from(hash.as_str());
+        body["out"][0]["value"] = Value::from(index + 1);
+
+        Mock::given(method("GET"))
+            .and(path(format!("/rawtx/{hash}")))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(body)
+                    .set_delay(Duration::from_secs(delay)),
+            )
+            .mount(&server)
+            .await;
+    }
+
+    let started = std::time::Instant::now();
+    let output = btc_analyser(
+        &server,
+        &[
+            "-e",
+            "inspect",
+            "-i",
+            &hashes.join(","),
+            "--show",
+            "total-output",
+            "--satoshis",
+            "--concurrency",
+            "3",
+        ],
+    )
+    .await;
+
+    assert!(output.status.success(), "{output:?}");
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "477401\n477402\n477403\n"
+    );
+    // One after the other they would take 4s
+    assert!(started.elapsed() < Duration::from_millis(3500));
+}
+
+#[tokio::test]
+async fn history_is_fetched_page_by_page() {
+    let server = MockServer::start().await;
+    mock_fixture(&server, &format!("/rawaddr/{ADDRESS}"), "address.json").await;
+
+    let output = btc_analyser(&server, &["-e", "address", "-a", ADDRESS, "--history"]).await;
+    let history = stdout_json(&output);
+
+    assert_eq!(history["address"]["address"], ADDRESS);
+    assert_eq!(history["history"].as_array().unwrap().len(), 2);
+    assert_eq!(history["history"][0]["received"], 477_400);
+    assert_eq!(history["history"][0]["sent"], 1_500_000);
+
+    let requests = server.received_requests().await.unwrap();
+    assert!(requests
+        .iter()
+        .any(|request| request.url.query() == Some("limit=50&offset=0")));
+}
+
+#[tokio::test]
+async fn history_since_block_skips_the_older_transactions() {
+    let server = MockServer::start().await;
+    mock_fixture(&server, &format!("/rawaddr/{ADDRESS}"), "address.json").await;
+
+    let output = btc_analyser(
+        &server,
+        &[
+            "-e",
+            "address",
+            "-a",
+            ADDRESS,
+            "--history",
+            "--since-block",
+            "820000",
+        ],
+    )
+    .await;
+    let history = stdout_json(&output);
+
+    assert_eq!(history["history"].as_array().unwrap().len(), 1);
+    assert_eq!(history["history"][0]["block_height"], 820_001);
+}
+
+#[tokio::test]
+async fn timed_out_request_is_retried() {
+    let server = MockServer::start().await;
+    // The first answer comes after --timeout, as on a flaky network
+    Mock::given(method("GET"))
+        .and(path(format!("/rawtx/{TRANSACTION_HASH}")))
+        .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_secs(3)))
+        .up_to_n_times(1)
+        .with_priority(1)
+        .mount(&server)
+        .await;
+    mock_fixture(
+        &server,
+        &format!("/rawtx/{TRANSACTION_HASH}"),
+        "transaction.json",
+    )
+    .await;
+
+    let output = btc_analyser(
+        &server,
+        &["-e", "inspect", "-i", TRANSACTION_HASH, "--timeout", "1"],
+    )
+    .await;
+
+    assert_eq!(stdout_json(&output)["hash"], TRANSACTION_HASH);
+    assert!(String::from_utf8_lossy(&output.stderr).contains("retry 1/3"));
+}
+
+#[tokio::test]
+async fn ping_reports_the_chain_tip() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/q/getblockcount"))
+        .respond_with(ResponseTemplate::new(200).set_body_string("820000"))
+        .mount(&server)
+        .await;
+
+    let ping = stdout_json(&btc_analyser(&server, &["-e", "ping"]).await);
+
+    assert_eq!(ping["reachable"], true);
+    assert_eq!(ping["block_height"], 820_000);
+
+    // Nothing answers on an unmocked path
+    let output = btc_analyser(&MockServer::start().await, &["-e", "ping"]).await;
+    assert!(!output.status.success());
+}
+
+#[tokio::test]
+async fn blocks_are_summarized_with_the_range_totals() {
+    let server = MockServer::start().await;
+    for height in [820_000, 820_001] {
+        Mock::given(method("GET"))
+            .and(path(format!("/block-height/{height}")))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "blocks": [{
+                    "hash": format!("block{height}"),
+                    "height": height,
+                    "time": 1_700_000_000,
+                    "tx": [
+                        {"hash": "coinbase", "inputs": [{}], "out": [{"value": 312_500_000}]},
+                        {
+                            "hash": "payment",
+                            "inputs": [{"prev_out": {"value": 100_000}}],
+                            "out": [{"value": 90_000}],
+                            "weight": 400
+                        }
+                    ]
+                }]
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+    }
+
+    let output = btc_analyser(
+        &server,
+        &["-e", "blocks", "--from", "820000", "--to", "820001"],
+    )
+    .await;
+    let blocks = stdout_json(&output);
+
+    assert_eq!(blocks["blocks"][1]["height"], 820_001);
+    assert_eq!(blocks["totals"]["fees_satoshis"], 20_000);
+    assert_eq!(blocks["totals"]["moved_satoshis"], 180_000);
+
+    let output = btc_analyser(
+        &server,
+        &["-e", "blocks", "--from", "820000", "--to", "830000"],
+    )
+    .await;
+    assert!(!output.status.success());
+}
+
+#[tokio::test]
+async fn redirects_are_followed_up_to_a_limit() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/q/getblockcount"))
+        .respond_with(ResponseTemplate::new(301).insert_header("Location", "/moved/getblockcount"))
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/moved/getblockcount"))
+        .respond_with(ResponseTemplate::new(200).set_body_string("820000"))
+        .mount(&server)
+        .await;
+
+    let ping = stdout_json(&btc_analyser(&server, &["-e", "ping"]).await);
+    assert_eq!(ping["block_height"], 820_000);
+
+    // A redirect loop gives up with a clear error
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/q/getblockcount"))
+        .respond_with(ResponseTemplate::new(302).insert_header("Location", "/q/getblockcount"))
+        .mount(&server)
+        .await;
+
+    let output = btc_analyser(&server, &["-e", "ping"]).await;
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("redirected more than"));
+}
+
+#[tokio::test]
+async fn dry_run_makes_no_request() {
+    let server = MockServer::start().await;
+
+    let dry_run =
+        stdout_json(&btc_analyser(&server, &["-e", "address", "-a", ADDRESS, "--dry-run"]).await);
+    assert_eq!(
+        dry_run["requests"][0],
+        format!("{}/rawaddr/{ADDRESS}", server.uri())
+    );
+    assert!(server.received_requests().await.unwrap().is_empty());
+
+    let output = btc_analyser(&server, &["-e", "inspect", "-i", "nonsense", "--dry-run"]).await;
+    assert!(!output.status.success());
+}